@@ -0,0 +1,319 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parse_json, parse_rxcui, IdType, RxNormClient, RxNormError, TermType, FALLBACK_CANDIDATES,
+};
+
+/// A candidate concept returned by `getApproximateMatch`.
+///
+/// Some candidates only identify an atom (`rxaui`) and carry no `rxcui`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApproximateCandidate {
+    pub rxcui: Option<u32>,
+    pub rxaui: Option<u32>,
+    /// Match score, rounded to the nearest integer.
+    pub score: i32,
+    pub rank: i32,
+    pub name: Option<String>,
+}
+
+impl RxNormClient {
+    /// Finds concepts whose names approximately match `term`, such as
+    /// misspelled or partial drug names.
+    ///
+    /// Candidates are returned in RxNav's rank order. RxNav lists candidates
+    /// of equal rank in no fixed order, so those are ordered by score, then
+    /// by ascending RxCUI, atom-only candidates last, then by name: the same
+    /// response always gives the same order.
+    pub async fn approximate_match(
+        &self,
+        term: &str,
+        max_entries: u32,
+    ) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+        let url = self.url("/approximateTerm.json");
+        let max_entries = max_entries.to_string();
+        let body = self
+            .get(&url, &[("term", term), ("maxEntries", &max_entries)])
+            .await?;
+        parse_candidates(&body)
+    }
+
+    /// Like [`approximate_match`](Self::approximate_match), but keeps only
+    /// candidates whose concept has a term type in `allowed_ttys`, such as
+    /// `&[TermType::In, TermType::Scd]`, in the same order.
+    ///
+    /// This costs one properties request per distinct candidate RxCUI on top
+    /// of the approximate search. Candidates that only identify an atom, or
+    /// whose RxCUI RxNav has no properties for, are dropped.
+    pub async fn approximate_match_filtered(
+        &self,
+        term: &str,
+        max_entries: u32,
+        allowed_ttys: &[TermType],
+    ) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+        let candidates = self.approximate_match(term, max_entries).await?;
+        let mut allowed: HashMap<u32, bool> = HashMap::new();
+        let mut kept = Vec::new();
+        for candidate in candidates {
+            let rxcui = match candidate.rxcui {
+                Some(rxcui) => rxcui,
+                None => continue,
+            };
+            let is_allowed = match allowed.get(&rxcui) {
+                Some(&is_allowed) => is_allowed,
+                None => {
+                    let props = self.get_properties(rxcui).await?;
+                    let is_allowed = props.is_some_and(|props| {
+                        allowed_ttys.contains(&TermType::from(props.tty.as_str()))
+                    });
+                    allowed.insert(rxcui, is_allowed);
+                    is_allowed
+                }
+            };
+            if is_allowed {
+                kept.push(candidate);
+            }
+        }
+        Ok(kept)
+    }
+
+    /// The single most likely RxCUI for `term`: the best approximate
+    /// candidate scoring at least `min_score`. Candidates that only identify
+    /// an atom are skipped. Equal scores go to the better rank, then to the
+    /// lower RxCUI, so the choice never depends on RxNav's response order.
+    pub async fn best_approximate_rxcui(
+        &self,
+        term: &str,
+        min_score: i32,
+    ) -> Result<Option<u32>, RxNormError> {
+        let candidates = self.approximate_match(term, FALLBACK_CANDIDATES).await?;
+        Ok(best_rxcui(&candidates, min_score).map(|(rxcui, _)| rxcui))
+    }
+
+    /// Finds the concept an atom belongs to, e.g. for an
+    /// [`ApproximateCandidate`] that only carries an `rxaui`. This is an
+    /// id search with the `RXAUI` id type; `None` means RxNav does not know
+    /// the atom.
+    pub async fn rxcui_for_rxaui(&self, rxaui: u32) -> Result<Option<u32>, RxNormError> {
+        let ids = self
+            .find_rxcui_by_id(&rxaui.to_string(), IdType::from(RXAUI))
+            .await?;
+        Ok(ids.and_then(|ids| ids.first().copied()))
+    }
+}
+
+/// The id type RxNav uses for RxNorm atom ids.
+pub(crate) const RXAUI: &str = "RXAUI";
+
+#[derive(Deserialize)]
+struct ApproximateResponse {
+    #[serde(rename = "approximateGroup", default)]
+    approximate_group: ApproximateGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct ApproximateGroup {
+    #[serde(default)]
+    candidate: Vec<RawCandidate>,
+}
+
+#[derive(Deserialize)]
+struct RawCandidate {
+    rxcui: Option<String>,
+    rxaui: Option<String>,
+    score: String,
+    rank: String,
+    name: Option<String>,
+}
+
+pub(crate) fn parse_candidates(body: &str) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+    let response: ApproximateResponse = parse_json(body)?;
+    let mut candidates = response
+        .approximate_group
+        .candidate
+        .into_iter()
+        .map(|c| {
+            Ok(ApproximateCandidate {
+                rxcui: parse_optional_id(c.rxcui)?,
+                rxaui: parse_optional_id(c.rxaui)?,
+                score: parse_number(&c.score)?,
+                rank: parse_number(&c.rank)?,
+                name: c.name.filter(|n| !n.is_empty()),
+            })
+        })
+        .collect::<Result<Vec<_>, RxNormError>>()?;
+    candidates.sort_by(|a, b| {
+        let key = |c: &ApproximateCandidate| (c.rank, Reverse(c.score), c.rxcui.is_none(), c.rxcui);
+        key(a).cmp(&key(b)).then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(candidates)
+}
+
+fn parse_optional_id(id: Option<String>) -> Result<Option<u32>, RxNormError> {
+    match id {
+        Some(id) if !id.trim().is_empty() => parse_rxcui(&id).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// The RxCUI and score of the best candidate that has an RxCUI and scores at
+/// least `min_score`. Equal scores are decided by RxNav's rank, then by the
+/// lower RxCUI.
+pub(crate) fn best_rxcui(
+    candidates: &[ApproximateCandidate],
+    min_score: i32,
+) -> Option<(u32, i32)> {
+    candidates
+        .iter()
+        .filter(|c| c.score >= min_score)
+        .filter_map(|c| c.rxcui.map(|rxcui| (rxcui, c.score, c.rank)))
+        .min_by_key(|&(rxcui, score, rank)| (Reverse(score), rank, rxcui))
+        .map(|(rxcui, score, _)| (rxcui, score))
+}
+
+fn parse_number(value: &str) -> Result<i32, RxNormError> {
+    value
+        .trim()
+        .parse::<f64>()
+        .map(|v| v.round() as i32)
+        .map_err(|_| RxNormError::JsonParse(format!("invalid number '{}'", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve_recording};
+
+    #[test]
+    fn test_parse_candidates_keeps_rank_order() {
+        let body = r#"{"approximateGroup":{"inputTerm":null,"candidate":[
+            {"rxcui":"1151","rxaui":"8317050","score":"10.2","rank":"1","name":"ascorbic acid","source":"RXNORM"},
+            {"rxcui":"","rxaui":"2179624","score":"8.7","rank":"2","source":"MMSL"}]}}"#;
+        let candidates = parse_candidates(body).unwrap();
+        assert_eq!(2, candidates.len());
+        assert_eq!(Some(1151), candidates[0].rxcui);
+        assert_eq!(10, candidates[0].score);
+        assert_eq!(None, candidates[1].rxcui);
+        assert_eq!(Some(2179624), candidates[1].rxaui);
+        assert_eq!(2, candidates[1].rank);
+    }
+
+    #[test]
+    fn test_best_rxcui_skips_atom_only_candidates() {
+        let body = r#"{"approximateGroup":{"candidate":[
+            {"rxcui":"","rxaui":"2179624","score":"9","rank":"1"},
+            {"rxcui":"1151","rxaui":"8317050","score":"8.7","rank":"2"}]}}"#;
+        let candidates = parse_candidates(body).unwrap();
+        assert_eq!(Some((1151, 9)), best_rxcui(&candidates, 5));
+        assert_eq!(None, best_rxcui(&candidates, 10));
+    }
+
+    #[test]
+    fn test_best_rxcui_breaks_ties_by_rank() {
+        let body = r#"{"approximateGroup":{"candidate":[
+            {"rxcui":"1151","score":"9","rank":"2"},
+            {"rxcui":"1088438","score":"9","rank":"1"},
+            {"rxcui":"2","score":"4","rank":"3"}]}}"#;
+        let candidates = parse_candidates(body).unwrap();
+        assert_eq!(Some((1088438, 9)), best_rxcui(&candidates, 0));
+    }
+
+    #[test]
+    fn test_equal_candidates_are_ordered_by_rxcui_then_name() {
+        let body = r#"{"approximateGroup":{"candidate":[
+            {"rxcui":"","rxaui":"2179624","score":"9","rank":"1","name":"vitamin c"},
+            {"rxcui":"1151","score":"9","rank":"1","name":"vitamin C"},
+            {"rxcui":"1151","score":"9","rank":"1","name":"ascorbic acid"},
+            {"rxcui":"1088438","score":"9","rank":"1","name":"Vitamin C"},
+            {"rxcui":"2","score":"4","rank":"2"}]}}"#;
+        let candidates = parse_candidates(body).unwrap();
+        let order: Vec<(Option<u32>, Option<&str>)> = candidates
+            .iter()
+            .map(|c| (c.rxcui, c.name.as_deref()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(1151), Some("ascorbic acid")),
+                (Some(1151), Some("vitamin C")),
+                (Some(1088438), Some("Vitamin C")),
+                (None, Some("vitamin c")),
+                (Some(2), None),
+            ],
+            order
+        );
+        let mut reversed = candidates.clone();
+        reversed.reverse();
+        assert_eq!(Some((1151, 9)), best_rxcui(&reversed, 0));
+    }
+
+    #[test]
+    fn test_parse_no_candidates() {
+        let body = r#"{"approximateGroup":{"inputTerm":null}}"#;
+        assert!(parse_candidates(body).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_filtered_match_keeps_allowed_term_types_in_order() {
+        let props = |rxcui: &str, tty: &str| {
+            response(
+                "200 OK",
+                &[],
+                &format!(
+                    r#"{{"properties":{{"rxcui":"{}","name":"x","synonym":"","tty":"{}","language":"ENG","suppress":"N","umlscui":""}}}}"#,
+                    rxcui, tty
+                ),
+            )
+        };
+        let (addr, requests) = serve_recording(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"{"approximateGroup":{"candidate":[
+                    {"rxcui":"1151","score":"10","rank":"1","name":"ascorbic acid"},
+                    {"rxcui":"","rxaui":"2179624","score":"9","rank":"2"},
+                    {"rxcui":"1088438","score":"9","rank":"3","name":"Vitamin C"},
+                    {"rxcui":"1151","score":"8","rank":"4","name":"vitamin C"},
+                    {"rxcui":"315266","score":"7","rank":"5"}]}}"#,
+            ),
+            props("1151", "IN"),
+            props("1088438", "BN"),
+            props("315266", "SCDC"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let kept = rx_client
+            .approximate_match_filtered("vitamin c", 5, &[TermType::In, TermType::Scdc])
+            .await
+            .unwrap();
+        let kept: Vec<(Option<u32>, i32)> = kept.iter().map(|c| (c.rxcui, c.rank)).collect();
+        assert_eq!(
+            vec![(Some(1151), 1), (Some(1151), 4), (Some(315266), 5)],
+            kept
+        );
+        assert_eq!(4, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_rxaui_is_searched_as_id() {
+        let (addr, requests) = serve_recording(vec![response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["1151"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .build();
+        assert_eq!(
+            Some(1151),
+            rx_client.rxcui_for_rxaui(8317050).await.unwrap()
+        );
+        assert!(requests.recv().unwrap().contains("idtype=RXAUI&id=8317050"));
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::{ConceptGroup, RxConceptProperties, RxNormClient, RxNormError, RxTermsInfo};
+
+/// Properties requests kept in flight by
+/// [`get_properties_batch`](RxNormClient::get_properties_batch) and
+/// [`resolve_detailed`](RxNormClient::resolve_detailed).
+const PROPERTIES_CONCURRENCY: usize = 4;
+
+/// RxTerms requests kept in flight by
+/// [`get_rxterms_batch`](RxNormClient::get_rxterms_batch).
+const RXTERMS_CONCURRENCY: usize = 4;
+
+/// Name searches kept in flight by
+/// [`resolve_union`](RxNormClient::resolve_union).
+const UNION_CONCURRENCY: usize = 4;
+
+/// A drug string and the result of searching for it.
+type TermResult = (String, Result<Option<Vec<u32>>, RxNormError>);
+
+/// Counts of how a batch of name searches, such as the results of
+/// [`find_rxcui_batch`](RxNormClient::find_rxcui_batch), turned out.
+///
+/// Its `Display` form is a one-line report for the end of a job, e.g.
+/// `normalized 9812/10000, 40 errors (12 timeout, 28 rate_limited)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    /// Terms that matched at least one RxCUI.
+    pub resolved: usize,
+    /// Terms RxNav has no match for.
+    pub not_found: usize,
+    /// The terms whose search failed, with the [`kind`](RxNormError::kind)
+    /// of each error, in result order.
+    pub errored: Vec<(String, &'static str)>,
+}
+
+impl BatchSummary {
+    pub fn from_results(results: &[TermResult]) -> Self {
+        let mut summary = Self::default();
+        for (term, result) in results {
+            match result {
+                Ok(Some(_)) => summary.resolved += 1,
+                Ok(None) => summary.not_found += 1,
+                Err(e) => summary.errored.push((term.clone(), e.kind())),
+            }
+        }
+        summary
+    }
+
+    /// The number of terms summarized.
+    pub fn total(&self) -> usize {
+        self.resolved + self.not_found + self.errored.len()
+    }
+
+    /// How many errors there were of each kind.
+    pub fn error_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for (_, kind) in &self.errored {
+            *counts.entry(*kind).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "normalized {}/{}", self.resolved, self.total())?;
+        if self.errored.is_empty() {
+            return Ok(());
+        }
+        let counts: Vec<String> = self
+            .error_counts()
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        write!(f, ", {} errors ({})", self.errored.len(), counts.join(", "))
+    }
+}
+
+impl RxNormClient {
+    /// Resolves many drug strings with at most `concurrency` requests in flight.
+    ///
+    /// Results are returned in completion order, each paired with the term it
+    /// belongs to, and can be tallied with [`BatchSummary::from_results`]. A
+    /// `concurrency` of zero is treated as one.
+    pub async fn find_rxcui_batch(
+        &self,
+        drugs: &[String],
+        concurrency: usize,
+    ) -> Vec<(String, Result<Option<Vec<u32>>, RxNormError>)> {
+        self.find_rxcui_batch_with_progress(
+            drugs,
+            concurrency,
+            &CancellationToken::new(),
+            |_, _| {},
+        )
+        .await
+    }
+
+    /// Like [`find_rxcui_batch`](Self::find_rxcui_batch), but calls `progress`
+    /// with `(completed, total)` as each result arrives and stops starting
+    /// new requests once `cancel` is cancelled.
+    ///
+    /// Requests already in flight when `cancel` trips are allowed to finish,
+    /// and the results gathered so far are returned.
+    pub async fn find_rxcui_batch_with_progress<F>(
+        &self,
+        drugs: &[String],
+        concurrency: usize,
+        cancel: &CancellationToken,
+        mut progress: F,
+    ) -> Vec<(String, Result<Option<Vec<u32>>, RxNormError>)>
+    where
+        F: FnMut(usize, usize),
+    {
+        let total = drugs.len();
+        let mut completed = 0;
+        stream::iter(drugs)
+            .take_while(|_| future::ready(!cancel.is_cancelled()))
+            .map(|drug| async move { (drug.clone(), self.find_rxcui(drug).await) })
+            .buffer_unordered(concurrency.max(1))
+            .inspect(|_| {
+                completed += 1;
+                progress(completed, total);
+            })
+            .collect()
+            .await
+    }
+
+    /// Resolves drug strings from any iterator, such as the rows of a CSV
+    /// reader, with at most `concurrency` requests in flight.
+    ///
+    /// Terms are pulled from `drugs` only as capacity frees up, so memory
+    /// stays bounded however long the input is. Results are yielded in
+    /// completion order, each paired with its term. A `concurrency` of zero
+    /// is treated as one.
+    pub fn find_rxcui_stream<'a, I>(
+        &'a self,
+        drugs: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = (String, Result<Option<Vec<u32>>, RxNormError>)> + 'a
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        stream::iter(drugs)
+            .map(move |drug| async move {
+                let result = self.find_rxcui(&drug).await;
+                (drug, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    /// Fetches the RxTerms entries of many concepts, keyed by RxCUI, e.g. the
+    /// display names for an autocomplete list.
+    ///
+    /// Like [`get_properties_batch`](Self::get_properties_batch), this sends
+    /// one request per distinct RxCUI, a few at a time. RxCUIs without an
+    /// RxTerms entry are left out, and the first failed request fails the
+    /// batch.
+    pub async fn get_rxterms_batch(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<HashMap<u32, RxTermsInfo>, RxNormError> {
+        let distinct: BTreeSet<u32> = rxcuis.iter().copied().collect();
+        stream::iter(distinct)
+            .map(|rxcui| async move { Ok((rxcui, self.get_rxterms(rxcui).await?)) })
+            .buffer_unordered(RXTERMS_CONCURRENCY)
+            .try_filter_map(|(rxcui, info)| async move { Ok(info.map(|info| (rxcui, info))) })
+            .try_collect()
+            .await
+    }
+
+    /// Resolves every term, such as the synonyms making up a cohort
+    /// definition, and returns all the RxCUIs they match, sorted and each
+    /// given once.
+    ///
+    /// Terms that match nothing add nothing; the first failed search fails
+    /// the whole call.
+    pub async fn resolve_union(&self, drugs: &[String]) -> Result<BTreeSet<u32>, RxNormError> {
+        let mut union = BTreeSet::new();
+        for (_, result) in self.find_rxcui_batch(drugs, UNION_CONCURRENCY).await {
+            union.extend(result?.unwrap_or_default());
+        }
+        Ok(union)
+    }
+
+    /// Like [`find_rxcui_stream`](Self::find_rxcui_stream), but writes each
+    /// result to `writer` as a line of JSON as soon as it is ready, so memory
+    /// stays flat however many terms there are:
+    ///
+    /// ```text
+    /// {"term":"tylenol","rxcuis":[202433]}
+    /// {"term":"unobtainium","rxcuis":null}
+    /// {"term":"aspirin","rxcuis":null,"error":"request to RxNav timed out"}
+    /// ```
+    ///
+    /// A failed lookup is written as a line with an `error` and does not stop
+    /// the batch; only a failed write does. The writer is flushed at the end.
+    pub async fn find_rxcui_batch_to_writer<S, W>(
+        &self,
+        drugs: S,
+        concurrency: usize,
+        mut writer: W,
+    ) -> Result<(), RxNormError>
+    where
+        S: Stream<Item = String>,
+        W: AsyncWrite + Unpin,
+    {
+        let results = drugs
+            .map(|drug| async move {
+                let result = self.find_rxcui(&drug).await;
+                (drug, result)
+            })
+            .buffer_unordered(concurrency.max(1));
+        futures::pin_mut!(results);
+        while let Some((term, result)) = results.next().await {
+            let line = match &result {
+                Ok(rxcuis) => NdjsonLine {
+                    term: &term,
+                    rxcuis: rxcuis.as_deref(),
+                    error: None,
+                },
+                Err(e) => NdjsonLine {
+                    term: &term,
+                    rxcuis: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            let mut json =
+                serde_json::to_vec(&line).map_err(|e| RxNormError::JsonParse(e.to_string()))?;
+            json.push(b'\n');
+            writer.write_all(&json).await?;
+        }
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Fetches the concepts related to each of many RxCUIs with the given
+    /// term types, keyed by source RxCUI, with at most `concurrency`
+    /// requests in flight, e.g. to expand one level of a graph crawl.
+    ///
+    /// Each distinct RxCUI is looked up once through
+    /// [`get_related_by_type`](Self::get_related_by_type), so cached results
+    /// are reused and the rate limit still applies. The first failed request
+    /// fails the batch. A `concurrency` of zero is treated as one.
+    pub async fn get_related_by_type_batch<T: AsRef<str> + Sync>(
+        &self,
+        rxcuis: &[u32],
+        ttys: &[T],
+        concurrency: usize,
+    ) -> Result<HashMap<u32, Vec<ConceptGroup>>, RxNormError> {
+        let distinct: BTreeSet<u32> = rxcuis.iter().copied().collect();
+        stream::iter(distinct)
+            .map(|rxcui| async move { Ok((rxcui, self.get_related_by_type(rxcui, ttys).await?)) })
+            .buffer_unordered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Fetches the properties of many concepts, keyed by RxCUI.
+    ///
+    /// RxNav has no multi-id form of the properties endpoint, so this sends
+    /// one request per distinct RxCUI, a few at a time. RxCUIs RxNav does not
+    /// know are left out, and the first failed request fails the batch.
+    pub async fn get_properties_batch(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<HashMap<u32, RxConceptProperties>, RxNormError> {
+        let distinct: BTreeSet<u32> = rxcuis.iter().copied().collect();
+        stream::iter(distinct)
+            .map(|rxcui| async move { Ok((rxcui, self.get_properties(rxcui).await?)) })
+            .buffer_unordered(PROPERTIES_CONCURRENCY)
+            .try_filter_map(|(rxcui, props)| async move { Ok(props.map(|props| (rxcui, props))) })
+            .try_collect()
+            .await
+    }
+
+    /// Finds the RxCUIs for `drug` with [`find_rxcui`](Self::find_rxcui) and
+    /// fetches the properties of each, a few at a time, for reports that
+    /// show every match with its name and term type.
+    ///
+    /// Matches keep `find_rxcui`'s order. A drug with no matches gives an
+    /// empty vec, matches without properties are left out, and the first
+    /// failed request fails the call.
+    pub async fn resolve_detailed(
+        &self,
+        drug: &str,
+    ) -> Result<Vec<RxConceptProperties>, RxNormError> {
+        let rxcuis = self.find_rxcui(drug).await?.unwrap_or_default();
+        stream::iter(rxcuis)
+            .map(|rxcui| self.get_properties(rxcui))
+            .buffered(PROPERTIES_CONCURRENCY)
+            .try_filter_map(future::ok)
+            .try_collect()
+            .await
+    }
+}
+
+/// One line written by
+/// [`find_rxcui_batch_to_writer`](RxNormClient::find_rxcui_batch_to_writer).
+#[derive(Serialize)]
+struct NdjsonLine<'a> {
+    term: &'a str,
+    rxcuis: Option<&'a [u32]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_batch_keeps_terms_with_results() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let drugs = vec![String::from("aspirin"), String::from("vit-c")];
+        let mut results = rx_client.find_rxcui_batch(&drugs, 2).await;
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(2, results.len());
+        assert_eq!("aspirin", results[0].0);
+        assert_eq!("vit-c", results[1].0);
+        assert!(results.iter().all(|(_, r)| r.is_err()));
+    }
+
+    #[test]
+    fn test_summary_counts_outcomes_and_error_kinds() {
+        use reqwest::StatusCode;
+
+        let results = vec![
+            (String::from("tylenol"), Ok(Some(vec![202433]))),
+            (String::from("unobtainium"), Ok(None)),
+            (String::from("aspirin"), Err(RxNormError::Timeout)),
+            (
+                String::from("ibuprofen"),
+                Err(RxNormError::UnexpectedStatus(StatusCode::TOO_MANY_REQUESTS)),
+            ),
+            (String::from("vit-c"), Err(RxNormError::Timeout)),
+        ];
+        let summary = BatchSummary::from_results(&results);
+        assert_eq!(1, summary.resolved);
+        assert_eq!(1, summary.not_found);
+        assert_eq!(
+            ("ibuprofen".to_string(), "rate_limited"),
+            summary.errored[1]
+        );
+        assert_eq!(
+            "normalized 1/5, 3 errors (1 rate_limited, 2 timeout)",
+            summary.to_string()
+        );
+        assert_eq!("normalized 0/0", BatchSummary::default().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_progress_and_stops_when_cancelled() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let drugs = vec![String::from("aspirin"), String::from("vit-c")];
+        let mut seen = Vec::new();
+        let cancel = CancellationToken::new();
+        let results = rx_client
+            .find_rxcui_batch_with_progress(&drugs, 1, &cancel, |done, total| {
+                seen.push((done, total))
+            })
+            .await;
+        assert_eq!(2, results.len());
+        assert_eq!(vec![(1, 2), (2, 2)], seen);
+        cancel.cancel();
+        let results = rx_client
+            .find_rxcui_batch_with_progress(&drugs, 1, &cancel, |_, _| {})
+            .await;
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_pulls_terms_from_iterator() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let drugs = ["aspirin", "vit-c", "tylenol"]
+            .iter()
+            .map(|d| d.to_string());
+        let mut results: Vec<_> = rx_client.find_rxcui_stream(drugs, 2).collect().await;
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        let terms: Vec<&str> = results.iter().map(|(term, _)| term.as_str()).collect();
+        assert_eq!(vec!["aspirin", "tylenol", "vit-c"], terms);
+        assert!(results.iter().all(|(_, r)| r.is_err()));
+    }
+
+    #[tokio::test]
+    async fn test_properties_batch_fails_on_error() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let actual = rx_client.get_properties_batch(&[1151, 161, 1151]).await;
+        assert!(matches!(actual, Err(RxNormError::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_detailed_adds_properties_to_each_match() {
+        use crate::test_server::{response, serve};
+
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["161"]}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"properties":{"rxcui":"161","name":"acetaminophen","synonym":"","tty":"IN","language":"ENG","suppress":"N","umlscui":""}}"#,
+            ),
+            response("200 OK", &[], r#"{"idGroup":{}}"#),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .normalize(false)
+            .max_retries(0)
+            .build();
+        let details = rx_client.resolve_detailed("acetaminophen").await.unwrap();
+        let summary: Vec<(u32, &str, &str)> = details
+            .iter()
+            .map(|p| (p.rxcui, p.name.as_str(), p.tty.as_str()))
+            .collect();
+        assert_eq!(vec![(161, "acetaminophen", "IN")], summary);
+        assert!(rx_client
+            .resolve_detailed("unobtainium")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rxterms_batch_fetches_each_id_once() {
+        use crate::test_server::{response, serve_recording};
+
+        let (addr, requests) = serve_recording(vec![response(
+            "200 OK",
+            &[],
+            r#"{"rxtermsProperties":{"displayName":"Acetaminophen (Oral Pill)","rxcui":"198440"}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let infos = rx_client
+            .get_rxterms_batch(&[198440, 198440])
+            .await
+            .unwrap();
+        assert_eq!("Acetaminophen (Oral Pill)", infos[&198440].display_name);
+        assert_eq!(1, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_related_batch_keys_groups_by_source() {
+        use crate::test_server::{response, serve_recording};
+        use crate::TermType;
+
+        let (addr, requests) = serve_recording(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[
+                    {"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[
+                    {"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build()
+            .with_cache(8);
+        let related = rx_client
+            .get_related_by_type_batch(&[198440, 209387, 198440], &[TermType::In], 2)
+            .await
+            .unwrap();
+        assert_eq!(2, related.len());
+        assert_eq!(161, related[&209387][0].concepts[0].rxcui);
+        let again = rx_client
+            .get_related_by_type_batch(&[209387], &[TermType::In], 0)
+            .await
+            .unwrap();
+        assert_eq!(related[&209387], again[&209387]);
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_union_skips_terms_without_matches() {
+        let mut fixtures = HashMap::new();
+        fixtures.insert(String::from("tylenol"), Some(vec![202433, 161]));
+        fixtures.insert(String::from("acetaminophen"), Some(vec![161]));
+        fixtures.insert(String::from("paracetamol"), None);
+        let rx_client = RxNormClient::with_fixtures(fixtures);
+        let drugs = vec![
+            String::from("tylenol"),
+            String::from("acetaminophen"),
+            String::from("paracetamol"),
+        ];
+        let union = rx_client.resolve_union(&drugs).await.unwrap();
+        assert_eq!(vec![161, 202433], union.into_iter().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_batch_writes_one_json_line_per_term() {
+        let mut fixtures = HashMap::new();
+        fixtures.insert(String::from("tylenol"), Some(vec![202433]));
+        fixtures.insert(String::from("unobtainium"), None);
+        let rx_client = RxNormClient::with_fixtures(fixtures);
+        let drugs = stream::iter(vec![
+            String::from("tylenol"),
+            String::from("unobtainium"),
+            String::from("aspirin"),
+        ]);
+        let mut out = Vec::new();
+        rx_client
+            .find_rxcui_batch_to_writer(drugs, 1, &mut out)
+            .await
+            .unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+        assert_eq!(
+            vec![
+                r#"{"term":"tylenol","rxcuis":[202433]}"#,
+                r#"{"term":"unobtainium","rxcuis":null}"#,
+                r#"{"term":"aspirin","rxcuis":null,"error":"no fixture for 'aspirin'"}"#,
+            ],
+            lines
+        );
+    }
+}
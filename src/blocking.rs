@@ -0,0 +1,755 @@
+//! A synchronous client for callers that do not run an async runtime.
+//!
+//! Enabled with the `blocking` feature. It shares URL building and response
+//! parsing with [`RxNormClient`](crate::RxNormClient), so both return the same
+//! results for the same request.
+
+use std::io::Read;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{ACCEPT_LANGUAGE, CONTENT_TYPE, USER_AGENT};
+
+use crate::approximate::{best_rxcui, parse_candidates, RXAUI};
+use crate::concepts::parse_min_concepts;
+use crate::display::parse_display_terms;
+use crate::drugs::{filter_drugs, parse_brand_group, parse_drug_group};
+use crate::history::parse_history_status;
+use crate::interaction::{parse_interaction_list, parse_interactions};
+use crate::matcher::similarity;
+use crate::ndc::{normalize_ndc, parse_historical_ndcs, parse_ndc_properties};
+use crate::properties::{
+    excludes, join_values, outside_language, parse_filtered_rxcui, parse_prop_concepts,
+    parse_properties, parse_property_values, prop_param, synonyms,
+};
+use crate::rate_limit::{RateLimiter, ThrottleCounters};
+use crate::related::{
+    merge_groups, parse_all_related_group, parse_related_concepts, parse_related_group,
+    PRESCRIBABLE_TTYS,
+};
+use crate::rxclass::{parse_atc_classes, parse_class_members};
+use crate::rxterms::parse_rxterms;
+use crate::search::check_search_params;
+use crate::spelling::parse_suggestions;
+use crate::term_type::parse_term_types;
+use crate::version::parse_version;
+use crate::{
+    check_api_error, check_content, check_rxcui, is_throttled, join_list, join_url, limit_term,
+    normalize_term, parse_json, parse_rxnorm_ids, retry_after, rxcui_path, tidy_ids,
+    without_overridden, worth_exact_search, ApproximateCandidate, AtcClass, ConceptGroup,
+    ConceptStatus, HistoricalNdc, HistoryStatus, IdType, Interaction, MergedConcept, NdcProperties,
+    PropCategory, PropConcept, RelatedConcept, RequestObserver, ResponseFormat, RetryPolicy,
+    RxConceptProperties, RxNormClientBuilder, RxNormError, RxNormVersion, RxTermsInfo, SearchMode,
+    TermType, ThrottleStats, DEFAULT_BASE_URL, FALLBACK_CANDIDATES,
+};
+
+/// Blocking counterpart of [`RxNormClient`](crate::RxNormClient).
+///
+/// # Examples
+/// ```rust,no_run
+/// use rxnormalizer::RxNormClient;
+///
+/// let rx_client = RxNormClient::builder().build_blocking();
+/// let ids = rx_client.find_rxcui("vit-c").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct RxNormBlockingClient {
+    pub(crate) client: Client,
+    pub(crate) search_mode: SearchMode,
+    pub(crate) base_url: String,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) throttle: Arc<ThrottleCounters>,
+    pub(crate) approximate_fallback: bool,
+    pub(crate) min_approximate_score: i32,
+    pub(crate) sort_results: bool,
+    pub(crate) max_matches: Option<usize>,
+    pub(crate) exclude_suppressed: bool,
+    pub(crate) language: Option<String>,
+    pub(crate) exact_fallback: bool,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) observer: Option<Arc<dyn RequestObserver>>,
+    pub(crate) source_params: Vec<(&'static str, String)>,
+    pub(crate) max_response_bytes: Option<usize>,
+    pub(crate) max_name_length: Option<usize>,
+    pub(crate) truncate_long_names: bool,
+    pub(crate) default_params: Vec<(String, String)>,
+}
+
+impl RxNormBlockingClient {
+    /// Creates a client using the given search mode. Passing a `bool` keeps
+    /// the historical behaviour: `true` searches exact then normalized.
+    pub fn new(client: Client, search_mode: impl Into<SearchMode>) -> Self {
+        Self::with_base_url(client, search_mode, DEFAULT_BASE_URL)
+    }
+
+    /// Creates a client that talks to a different RxNav host.
+    pub fn with_base_url(
+        client: Client,
+        search_mode: impl Into<SearchMode>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        let mut rx_client = RxNormClientBuilder::default()
+            .search_mode(search_mode.into())
+            .base_url(base_url)
+            .build_blocking();
+        rx_client.client = client;
+        rx_client.user_agent = None;
+        rx_client
+    }
+
+    /// The HTTP client every request is sent with.
+    pub fn http_client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Blocking version of [`RxNormClient::throttle_stats`](crate::RxNormClient::throttle_stats).
+    pub fn throttle_stats(&self) -> ThrottleStats {
+        self.throttle.stats()
+    }
+
+    /// Blocking version of [`RxNormClient::find_rxcui`](crate::RxNormClient::find_rxcui).
+    pub fn find_rxcui(&self, drug: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let ids = match self.search_ids(&[("name", drug), ("search", self.search_mode.as_param())])
+        {
+            result
+                if self.exact_fallback
+                    && self.search_mode != SearchMode::Exact
+                    && worth_exact_search(&result) =>
+            {
+                self.search_ids(&[("name", drug), ("search", SearchMode::Exact.as_param())])?
+            }
+            result => result?,
+        };
+        if ids.is_some() || !self.approximate_fallback {
+            return self.without_suppressed(ids);
+        }
+        let candidates = self.approximate_match(drug, FALLBACK_CANDIDATES)?;
+        let ids = best_rxcui(&candidates, self.min_approximate_score).map(|(rxcui, _)| vec![rxcui]);
+        self.without_suppressed(ids)
+    }
+
+    /// Blocking version of [`RxNormClient::find_rxcui_raw`](crate::RxNormClient::find_rxcui_raw).
+    pub fn find_rxcui_raw(&self, drug: &str) -> Result<serde_json::Value, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let query = self.scoped(&[("name", drug), ("search", self.search_mode.as_param())])?;
+        let body = self.get("/rxcui.json", &query)?;
+        parse_json(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::find_rxcui_by_id`](crate::RxNormClient::find_rxcui_by_id).
+    pub fn find_rxcui_by_id(
+        &self,
+        id: &str,
+        idtype: IdType,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.search_ids(&[("idtype", idtype.as_str()), ("id", id.trim())])
+    }
+
+    /// Blocking version of [`RxNormClient::find_rxcui_by_ndc`](crate::RxNormClient::find_rxcui_by_ndc).
+    pub fn find_rxcui_by_ndc(&self, ndc: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.find_rxcui_by_id(&normalize_ndc(ndc), IdType::Ndc)
+    }
+
+    /// Blocking version of [`RxNormClient::get_historical_ndcs`](crate::RxNormClient::get_historical_ndcs).
+    pub fn get_historical_ndcs(&self, rxcui: u32) -> Result<Vec<HistoricalNdc>, RxNormError> {
+        let body = self.get(&rxcui_path(rxcui, "allhistoricalndcs.json")?, &[])?;
+        parse_historical_ndcs(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_ndc_properties`](crate::RxNormClient::get_ndc_properties).
+    pub fn get_ndc_properties(&self, ndc: &str) -> Result<Option<NdcProperties>, RxNormError> {
+        let body = self.get("/ndcproperties.json", &[("id", ndc.trim())])?;
+        parse_ndc_properties(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_properties`](crate::RxNormClient::get_properties).
+    pub fn get_properties(&self, rxcui: u32) -> Result<Option<RxConceptProperties>, RxNormError> {
+        let body = self.get(&rxcui_path(rxcui, "properties.json")?, &[])?;
+        let props = parse_properties(&body)?;
+        Ok(props.filter(|props| !outside_language(self.language.as_deref(), &props.language)))
+    }
+
+    /// Blocking version of [`RxNormClient::get_name`](crate::RxNormClient::get_name).
+    pub fn get_name(&self, rxcui: u32) -> Result<Option<String>, RxNormError> {
+        Ok(self.get_properties(rxcui)?.map(|props| props.name))
+    }
+
+    /// Blocking version of [`RxNormClient::verify_mapping`](crate::RxNormClient::verify_mapping).
+    pub fn verify_mapping(
+        &self,
+        input: &str,
+        rxcui: u32,
+        min_similarity: f32,
+    ) -> Result<bool, RxNormError> {
+        let name = self.get_name(rxcui)?;
+        Ok(name.is_some_and(|name| similarity(input, &name) >= min_similarity))
+    }
+
+    /// Blocking version of [`RxNormClient::get_all_properties`](crate::RxNormClient::get_all_properties).
+    pub fn get_all_properties(
+        &self,
+        rxcui: u32,
+        categories: &[PropCategory],
+    ) -> Result<Vec<PropConcept>, RxNormError> {
+        let prop = prop_param(categories);
+        let body = self.get(
+            &rxcui_path(rxcui, "allProperties.json")?,
+            &[("prop", &prop)],
+        )?;
+        parse_prop_concepts(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_synonyms`](crate::RxNormClient::get_synonyms).
+    pub fn get_synonyms(&self, rxcui: u32) -> Result<Vec<String>, RxNormError> {
+        let props = self.get_all_properties(rxcui, &[PropCategory::Names])?;
+        Ok(synonyms(props))
+    }
+
+    /// Blocking version of [`RxNormClient::get_property`](crate::RxNormClient::get_property).
+    pub fn get_property(&self, rxcui: u32, prop_name: &str) -> Result<Option<String>, RxNormError> {
+        Ok(join_values(self.get_property_values(rxcui, prop_name)?))
+    }
+
+    /// Blocking version of [`RxNormClient::get_property_values`](crate::RxNormClient::get_property_values).
+    pub fn get_property_values(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+    ) -> Result<Vec<String>, RxNormError> {
+        let body = self.get(
+            &rxcui_path(rxcui, "property.json")?,
+            &[("propName", prop_name)],
+        )?;
+        parse_property_values(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::filter_by_property`](crate::RxNormClient::filter_by_property).
+    pub fn filter_by_property(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+        prop_values: &[&str],
+    ) -> Result<Option<u32>, RxNormError> {
+        let prop_values = join_list(prop_values);
+        let body = self.get(
+            &rxcui_path(rxcui, "filter.json")?,
+            &[("propName", prop_name), ("propValues", &prop_values)],
+        )?;
+        parse_filtered_rxcui(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_rxterms`](crate::RxNormClient::get_rxterms).
+    pub fn get_rxterms(&self, rxcui: u32) -> Result<Option<RxTermsInfo>, RxNormError> {
+        let body = self.get(
+            &format!("/RxTerms{}", rxcui_path(rxcui, "allinfo.json")?),
+            &[],
+        )?;
+        parse_rxterms(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::approximate_match`](crate::RxNormClient::approximate_match).
+    pub fn approximate_match(
+        &self,
+        term: &str,
+        max_entries: u32,
+    ) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+        let max_entries = max_entries.to_string();
+        let body = self.get(
+            "/approximateTerm.json",
+            &[("term", term), ("maxEntries", &max_entries)],
+        )?;
+        parse_candidates(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::best_approximate_rxcui`](crate::RxNormClient::best_approximate_rxcui).
+    pub fn best_approximate_rxcui(
+        &self,
+        term: &str,
+        min_score: i32,
+    ) -> Result<Option<u32>, RxNormError> {
+        let candidates = self.approximate_match(term, FALLBACK_CANDIDATES)?;
+        Ok(best_rxcui(&candidates, min_score).map(|(rxcui, _)| rxcui))
+    }
+
+    /// Blocking version of [`RxNormClient::rxcui_for_rxaui`](crate::RxNormClient::rxcui_for_rxaui).
+    pub fn rxcui_for_rxaui(&self, rxaui: u32) -> Result<Option<u32>, RxNormError> {
+        let ids = self.find_rxcui_by_id(&rxaui.to_string(), IdType::from(RXAUI))?;
+        Ok(ids.and_then(|ids| ids.first().copied()))
+    }
+
+    /// Blocking version of [`RxNormClient::spelling_suggestions`](crate::RxNormClient::spelling_suggestions).
+    pub fn spelling_suggestions(&self, name: &str) -> Result<Vec<String>, RxNormError> {
+        let body = self.get("/spellingsuggestions.json", &[("name", name)])?;
+        parse_suggestions(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_related_by_type`](crate::RxNormClient::get_related_by_type).
+    pub fn get_related_by_type<T: AsRef<str>>(
+        &self,
+        rxcui: u32,
+        ttys: &[T],
+    ) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let tty = join_list(ttys.iter().map(AsRef::as_ref));
+        let body = self.get(&rxcui_path(rxcui, "related.json")?, &[("tty", &tty)])?;
+        Ok(self.without_dropped(parse_related_group(&body)?))
+    }
+
+    /// Blocking version of [`RxNormClient::get_related_by_type_merged`](crate::RxNormClient::get_related_by_type_merged).
+    pub fn get_related_by_type_merged<T: AsRef<str>>(
+        &self,
+        rxcui: u32,
+        ttys: &[T],
+    ) -> Result<Vec<MergedConcept>, RxNormError> {
+        Ok(merge_groups(self.get_related_by_type(rxcui, ttys)?))
+    }
+
+    /// Blocking version of [`RxNormClient::get_prescribable_forms`](crate::RxNormClient::get_prescribable_forms).
+    pub fn get_prescribable_forms(&self, rxcui: u32) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let groups = self.get_related_by_type(rxcui, &PRESCRIBABLE_TTYS)?;
+        Ok(groups
+            .into_iter()
+            .flat_map(|group| group.concepts)
+            .collect())
+    }
+
+    /// Blocking version of [`RxNormClient::get_related_by_rela`](crate::RxNormClient::get_related_by_rela).
+    pub fn get_related_by_rela(
+        &self,
+        rxcui: u32,
+        relas: &[&str],
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let rela = join_list(relas);
+        let body = self.get(&rxcui_path(rxcui, "related.json")?, &[("rela", &rela)])?;
+        let mut concepts = parse_related_concepts(&body)?;
+        concepts.retain(|concept| !self.drops(concept));
+        Ok(concepts)
+    }
+
+    /// Blocking version of [`RxNormClient::get_all_related`](crate::RxNormClient::get_all_related).
+    pub fn get_all_related(&self, rxcui: u32) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let body = self.get(&rxcui_path(rxcui, "allrelated.json")?, &[])?;
+        let mut groups = self.without_dropped(parse_all_related_group(&body)?);
+        groups.retain(|group| !group.concepts.is_empty());
+        Ok(groups)
+    }
+
+    /// Blocking version of [`RxNormClient::get_all_related_merged`](crate::RxNormClient::get_all_related_merged).
+    pub fn get_all_related_merged(&self, rxcui: u32) -> Result<Vec<MergedConcept>, RxNormError> {
+        Ok(merge_groups(self.get_all_related(rxcui)?))
+    }
+
+    /// Blocking version of [`RxNormClient::get_drugs`](crate::RxNormClient::get_drugs).
+    pub fn get_drugs(&self, name: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let body = self.get("/drugs.json", &[("name", name)])?;
+        parse_drug_group(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_drugs_filtered`](crate::RxNormClient::get_drugs_filtered).
+    pub fn get_drugs_filtered(
+        &self,
+        name: &str,
+        dose_form: Option<&str>,
+        route: Option<&str>,
+    ) -> Result<Vec<ConceptGroup>, RxNormError> {
+        Ok(filter_drugs(self.get_drugs(name)?, dose_form, route))
+    }
+
+    /// Blocking version of [`RxNormClient::get_brands_for_ingredients`](crate::RxNormClient::get_brands_for_ingredients).
+    pub fn get_brands_for_ingredients(
+        &self,
+        ingredient_rxcuis: &[u32],
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        if ingredient_rxcuis.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids = join_list(ingredient_rxcuis);
+        let body = self.get("/brands.json", &[("ingredientids", &ids)])?;
+        parse_brand_group(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::find_interactions`](crate::RxNormClient::find_interactions).
+    pub fn find_interactions(&self, rxcui: u32) -> Result<Vec<Interaction>, RxNormError> {
+        check_rxcui(rxcui)?;
+        let body = self.get(
+            "/interaction/interaction.json",
+            &[("rxcui", &rxcui.to_string())],
+        )?;
+        parse_interactions(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::find_interactions_between`](crate::RxNormClient::find_interactions_between).
+    pub fn find_interactions_between(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<Vec<Interaction>, RxNormError> {
+        if rxcuis.is_empty() {
+            return Ok(Vec::new());
+        }
+        let body = self.get("/interaction/list.json", &[("rxcuis", &join_list(rxcuis))])?;
+        parse_interaction_list(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_class_members`](crate::RxNormClient::get_class_members).
+    pub fn get_class_members(
+        &self,
+        class_id: &str,
+        rela_source: &str,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let query = [("classId", class_id), ("relaSource", rela_source)];
+        let body = self.get("/rxclass/classMembers.json", &query)?;
+        parse_class_members(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_atc_classes`](crate::RxNormClient::get_atc_classes).
+    pub fn get_atc_classes(&self, rxcui: u32) -> Result<Vec<AtcClass>, RxNormError> {
+        check_rxcui(rxcui)?;
+        let rxcui = rxcui.to_string();
+        let body = self.get(
+            "/rxclass/class/byRxcui.json",
+            &[("rxcui", &rxcui), ("relaSource", "ATC")],
+        )?;
+        parse_atc_classes(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::history_status`](crate::RxNormClient::history_status).
+    pub fn history_status(&self, rxcui: u32) -> Result<HistoryStatus, RxNormError> {
+        let body = self.get(&rxcui_path(rxcui, "historystatus.json")?, &[])?;
+        parse_history_status(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_term_types`](crate::RxNormClient::get_term_types).
+    pub fn get_term_types(&self) -> Result<Vec<String>, RxNormError> {
+        let body = self.get("/termtypes.json", &[])?;
+        parse_term_types(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_all_concepts`](crate::RxNormClient::get_all_concepts).
+    pub fn get_all_concepts(&self, ttys: &[TermType]) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let tty = join_list(ttys);
+        let body = self.get("/allconcepts.json", &[("tty", &tty)])?;
+        parse_min_concepts(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_concepts_by_status`](crate::RxNormClient::get_concepts_by_status).
+    pub fn get_concepts_by_status(
+        &self,
+        status: ConceptStatus,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let body = self.get("/allstatus.json", &[("status", status.as_str())])?;
+        parse_min_concepts(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_display_terms`](crate::RxNormClient::get_display_terms).
+    /// The blocking client has no cache, so every call downloads the list.
+    pub fn get_display_terms(&self) -> Result<Vec<String>, RxNormError> {
+        let body = self.get("/displaynames.json", &[])?;
+        parse_display_terms(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::get_version`](crate::RxNormClient::get_version).
+    pub fn get_version(&self) -> Result<RxNormVersion, RxNormError> {
+        let body = self.get("/version.json", &[])?;
+        parse_version(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::warm_up`](crate::RxNormClient::warm_up).
+    pub fn warm_up(&self) -> Result<(), RxNormError> {
+        self.get_version().map(|_| ())
+    }
+
+    /// Blocking version of [`RxNormClient::call_endpoint`](crate::RxNormClient::call_endpoint).
+    pub fn call_endpoint(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<serde_json::Value, RxNormError> {
+        let body = self.get(path, params)?;
+        parse_json(&body)
+    }
+
+    /// Blocking version of [`RxNormClient::call_endpoint_as`](crate::RxNormClient::call_endpoint_as).
+    pub fn call_endpoint_as(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+        format: ResponseFormat,
+    ) -> Result<String, RxNormError> {
+        self.get(&format.path(path), params)
+    }
+
+    /// Blocking version of [`RxNormClient::find_rxcui_raw_xml`](crate::RxNormClient::find_rxcui_raw_xml).
+    pub fn find_rxcui_raw_xml(&self, drug: &str) -> Result<String, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let query = self.scoped(&[("name", drug), ("search", self.search_mode.as_param())])?;
+        self.get(&ResponseFormat::Xml.path("/rxcui"), &query)
+    }
+
+    /// Blocking version of the async client's suppressed-concept filter:
+    /// `ids` without the concepts whose properties say they are suppressed.
+    fn without_suppressed(&self, ids: Option<Vec<u32>>) -> Result<Option<Vec<u32>>, RxNormError> {
+        let ids = match ids {
+            Some(ids) if self.exclude_suppressed => ids,
+            ids => return Ok(ids),
+        };
+        let mut kept = Vec::new();
+        for rxcui in ids {
+            let props = self.get_properties(rxcui)?;
+            if props.is_none_or(|props| !excludes(true, &props.suppress)) {
+                kept.push(rxcui);
+            }
+        }
+        Ok(Some(kept).filter(|kept| !kept.is_empty()))
+    }
+
+    /// Whether a related concept is left out as suppressed or in another
+    /// language.
+    fn drops(&self, concept: &RelatedConcept) -> bool {
+        excludes(self.exclude_suppressed, &concept.suppress)
+            || outside_language(self.language.as_deref(), &concept.language)
+    }
+
+    fn without_dropped(&self, mut groups: Vec<ConceptGroup>) -> Vec<ConceptGroup> {
+        for group in &mut groups {
+            group.concepts.retain(|concept| !self.drops(concept));
+        }
+        groups
+    }
+
+    fn search_ids(&self, query: &[(&str, &str)]) -> Result<Option<Vec<u32>>, RxNormError> {
+        let body = self.get("/rxcui.json", &self.scoped(query)?)?;
+        let ids = parse_rxnorm_ids(&body)?;
+        Ok(tidy_ids(ids, self.sort_results, self.max_matches))
+    }
+
+    /// Adds the configured source scope's parameters to a search query,
+    /// rejecting combinations RxNav would silently misread.
+    fn scoped<'a>(
+        &'a self,
+        query: &[(&'a str, &'a str)],
+    ) -> Result<Vec<(&'a str, &'a str)>, RxNormError> {
+        let scope = self.source_params.iter().map(|(k, v)| (*k, v.as_str()));
+        let query: Vec<_> = query.iter().copied().chain(scope).collect();
+        let defaults = without_overridden(&self.default_params, &query);
+        check_search_params(query.iter().chain(&defaults))?;
+        Ok(query)
+    }
+
+    /// `drug` normalized and held to the configured maximum name length.
+    fn prepare_term(&self, drug: &str) -> Result<String, RxNormError> {
+        limit_term(
+            normalize_term(drug)?,
+            self.max_name_length,
+            self.truncate_long_names,
+        )
+    }
+
+    /// Sends a GET request for `path`, retrying transport errors and throttled
+    /// responses as the retry policy allows, and returns the body of a
+    /// successful response.
+    fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<String, RxNormError> {
+        let url = join_url(&self.base_url, path);
+        let result = self.send(&url, query);
+        if let (Some(observer), Err(e)) = (&self.observer, &result) {
+            observer.on_error(&url, e);
+        }
+        result
+    }
+
+    fn send(&self, url: &str, query: &[(&str, &str)]) -> Result<String, RxNormError> {
+        let start = Instant::now();
+        let mut attempt = 0;
+        let res = loop {
+            let (error, retry_after, throttled) = match self.make_call(url, query) {
+                Ok(res) if is_throttled(res.status()) => {
+                    self.throttle.record_throttled();
+                    (
+                        RxNormError::UnexpectedStatus(res.status()),
+                        retry_after(res.headers()),
+                        Some(res),
+                    )
+                }
+                Ok(res) => break res,
+                Err(e) if !self.retry_policy.retries(&e) => return Err(RxNormError::from(e)),
+                Err(e) => (RxNormError::from(e), None, None),
+            };
+            attempt += 1;
+            let delay = match self
+                .retry_policy
+                .next_delay(attempt, start.elapsed(), retry_after)
+            {
+                Some(delay) => delay,
+                None => {
+                    debug!(
+                        "Request to {} with {:?} failed after {} attempts: {}",
+                        url, query, attempt, error
+                    );
+                    match throttled {
+                        Some(res) => break res,
+                        None => return Err(error),
+                    }
+                }
+            };
+            warn!(
+                "Request to {} with {:?} failed on attempt {}: {}, retrying in {:?}",
+                url, query, attempt, error, delay
+            );
+            self.throttle.record_retry(delay);
+            if let Some(observer) = &self.observer {
+                observer.on_retry(url, attempt, delay);
+            }
+            drop(throttled);
+            sleep(delay);
+        };
+        let status = res.status();
+        let content_type = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = read_body(res, self.max_response_bytes)?;
+        check_content(content_type.as_deref(), &body)?;
+        if !status.is_success() {
+            return Err(RxNormError::UnexpectedStatus(status));
+        }
+        check_api_error(&body)?;
+        Ok(body)
+    }
+
+    fn make_call(&self, url: &str, query: &[(&str, &str)]) -> Result<Response, reqwest::Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire_blocking();
+        }
+        let defaults = without_overridden(&self.default_params, query);
+        let mut request = self.client.get(url).query(query).query(&defaults);
+        if let Some(user_agent) = &self.user_agent {
+            request = request.header(USER_AGENT, user_agent);
+        }
+        if let Some(language) = &self.language {
+            request = request.header(ACCEPT_LANGUAGE, language.to_ascii_lowercase());
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        let request = request.build()?;
+        debug!("GET {}", request.url());
+        if let Some(observer) = &self.observer {
+            observer.on_request(url);
+        }
+        let sent = Instant::now();
+        let res = self.client.execute(request);
+        if let (Some(observer), Ok(res)) = (&self.observer, &res) {
+            observer.on_response(url, res.status(), sent.elapsed());
+        }
+        res
+    }
+}
+
+/// Reads a response body, failing with [`RxNormError::ResponseTooLarge`] once
+/// it grows past `limit` bytes.
+fn read_body(res: Response, limit: Option<usize>) -> Result<String, RxNormError> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(res.text()?),
+    };
+    let mut body = Vec::new();
+    res.take(limit as u64 + 1).read_to_end(&mut body)?;
+    if body.len() > limit {
+        return Err(RxNormError::ResponseTooLarge { limit });
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_exhausted_retries_return_error() {
+        let rx_client = RxNormClientBuilder::default()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(1)
+            .retry_backoff(Duration::from_millis(1))
+            .build_blocking();
+        let actual = rx_client.find_rxcui("vit-c");
+        assert!(matches!(actual, Err(RxNormError::Http(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blocking_client_applies_async_filters() {
+        use crate::test_server::{response, serve_recording};
+
+        let props = |rxcui: &str, suppress: &str| {
+            format!(
+                r#"{{"properties":{{"rxcui":"{}","name":"x","tty":"SCD","suppress":"{}"}}}}"#,
+                rxcui, suppress
+            )
+        };
+        let (addr, requests) = serve_recording(vec![
+            response("200 OK", &[], r#"{"idGroup":{}}"#),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1","2"]}}"#),
+            response("200 OK", &[], &props("1", "N")),
+            response("200 OK", &[], &props("2", "Y")),
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[
+                    {"rxcui":"161","name":"acetaminophen","tty":"IN","language":"ENG"},
+                    {"rxcui":"162","name":"acetaminofeno","tty":"IN","language":"SPA"}]}]}}"#,
+            ),
+        ])
+        .await;
+        let (ids, groups) = tokio::task::spawn_blocking(move || {
+            let rx_client = RxNormClientBuilder::default()
+                .base_url(format!("http://{}", addr))
+                .max_retries(0)
+                .search_mode(SearchMode::Normalized)
+                .fallback_to_exact_on_normalized_failure(true)
+                .exclude_suppressed(true)
+                .language("ENG")
+                .build_blocking();
+            (
+                rx_client.find_rxcui("x").unwrap(),
+                rx_client.get_related_by_type(1, &["IN"]).unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+        assert_eq!(Some(vec![1]), ids);
+        assert_eq!(1, groups[0].concepts.len());
+        assert_eq!(161, groups[0].concepts[0].rxcui);
+        assert!(requests.try_iter().nth(1).unwrap().contains("search=0"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blocking_all_related_drops_emptied_groups() {
+        use crate::test_server::{response, serve};
+
+        let addr = serve(vec![response(
+            "200 OK",
+            &[],
+            r#"{"allRelatedGroup":{"conceptGroup":[
+                {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","tty":"IN","suppress":"N"}]},
+                {"tty":"BN","conceptProperties":[{"rxcui":"202433","name":"Tylenol","tty":"BN","suppress":"Y"}]}]}}"#,
+        )])
+        .await;
+        let groups = tokio::task::spawn_blocking(move || {
+            RxNormClientBuilder::default()
+                .base_url(format!("http://{}", addr))
+                .max_retries(0)
+                .exclude_suppressed(true)
+                .build_blocking()
+                .get_all_related(161)
+                .unwrap()
+        })
+        .await
+        .unwrap();
+        assert_eq!(1, groups.len());
+        assert_eq!("IN", groups[0].tty);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::rate_limit::{InFlightLimit, RateLimiter};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    Backoff, RequestObserver, RetryPolicy, RxNormClient, SearchMode, SourceScope, DEFAULT_BASE_URL,
+    DEFAULT_MAX_NAME_LENGTH, DEFAULT_MAX_RESPONSE_BYTES, DEFAULT_USER_AGENT,
+};
+
+/// Builder for [`RxNormClient`], created with [`RxNormClient::builder`].
+///
+/// # Examples
+/// ```rust
+/// use rxnormalizer::RxNormClient;
+///
+/// let rx_client = RxNormClient::builder()
+///     .normalize(false)
+///     .base_url("http://localhost:4000/REST")
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct RxNormClientBuilder {
+    http_client: Option<reqwest::Client>,
+    search_mode: SearchMode,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    timeout: Option<Duration>,
+    max_requests_per_second: u32,
+    max_in_flight: usize,
+    approximate_fallback: bool,
+    exact_fallback: bool,
+    min_approximate_score: i32,
+    sort_results: bool,
+    max_matches: Option<usize>,
+    exclude_suppressed: bool,
+    language: Option<String>,
+    user_agent: Option<String>,
+    observer: Option<Arc<dyn RequestObserver>>,
+    source_scope: SourceScope,
+    max_response_bytes: usize,
+    max_name_length: usize,
+    truncate_long_names: bool,
+    default_params: Vec<(String, String)>,
+    min_version: Option<String>,
+}
+
+impl Default for RxNormClientBuilder {
+    fn default() -> Self {
+        Self {
+            http_client: None,
+            search_mode: SearchMode::default(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry_policy: RetryPolicy::default(),
+            timeout: None,
+            max_requests_per_second: 0,
+            max_in_flight: 0,
+            approximate_fallback: false,
+            exact_fallback: false,
+            min_approximate_score: 0,
+            sort_results: false,
+            max_matches: None,
+            exclude_suppressed: false,
+            language: None,
+            user_agent: None,
+            observer: None,
+            source_scope: SourceScope::default(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            max_name_length: DEFAULT_MAX_NAME_LENGTH,
+            truncate_long_names: false,
+            default_params: Vec::new(),
+            min_version: None,
+        }
+    }
+}
+
+impl RxNormClientBuilder {
+    /// Whether name searches fall back to RxNav's normalized search. `true`
+    /// is [`SearchMode::ExactThenNormalized`] and `false` is [`SearchMode::Exact`].
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.search_mode = normalize.into();
+        self
+    }
+
+    /// How name searches match drug names. Defaults to
+    /// [`SearchMode::ExactThenNormalized`].
+    pub fn search_mode(mut self, search_mode: SearchMode) -> Self {
+        self.search_mode = search_mode;
+        self
+    }
+
+    /// The RxNav REST root to send requests to. Defaults to [`DEFAULT_BASE_URL`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// The HTTP client to send every request with, keeping its proxies,
+    /// TLS settings, redirect policy and default headers. A default
+    /// `reqwest::Client` is created only when none is given.
+    ///
+    /// The client's own configuration is never overridden: a `User-Agent` is
+    /// only added when [`user_agent`](Self::user_agent) is set, and a timeout
+    /// only when [`timeout`](Self::timeout) is.
+    ///
+    /// # Examples
+    /// Reaching a self-hosted RxNav behind a proxy with a self-signed
+    /// certificate, without following redirects:
+    /// ```rust,no_run
+    /// use rxnormalizer::RxNormClient;
+    ///
+    /// let http_client = reqwest::Client::builder()
+    ///     .danger_accept_invalid_certs(true)
+    ///     .redirect(reqwest::redirect::Policy::none())
+    ///     .build()
+    ///     .unwrap();
+    /// let rx_client = RxNormClient::builder()
+    ///     .http_client(http_client)
+    ///     .base_url("https://rxnav.internal.example/REST")
+    ///     .build();
+    /// ```
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// How many times a request that failed to send or was throttled is
+    /// retried. Defaults to [`DEFAULT_MAX_RETRIES`](crate::DEFAULT_MAX_RETRIES).
+    /// Zero returns the first failure at once, without sleeping.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry_policy.max_retries = max_retries;
+        self
+    }
+
+    /// Never retries, for interactive use where a fast failure beats a slow
+    /// recovery. Shorthand for `max_retries(0)`.
+    pub fn no_retry(self) -> Self {
+        self.max_retries(0)
+    }
+
+    /// How long to wait before each retry, as a fixed delay. Defaults to
+    /// [`DEFAULT_RETRY_BACKOFF`](crate::DEFAULT_RETRY_BACKOFF).
+    pub fn retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_policy.backoff = Backoff::Fixed(retry_backoff);
+        self
+    }
+
+    /// How failed requests are retried, replacing any `max_retries` and
+    /// `retry_backoff` set before. Defaults to [`DEFAULT_MAX_RETRIES`](crate::DEFAULT_MAX_RETRIES)
+    /// retries with a fixed [`DEFAULT_RETRY_BACKOFF`](crate::DEFAULT_RETRY_BACKOFF).
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Maximum time a single request may take, including reading the body.
+    /// Requests have no timeout by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Caps how many requests, including retries, are started per second.
+    /// RxNav asks clients to stay under 20. Zero, the default, means no limit.
+    pub fn max_requests_per_second(mut self, max_requests_per_second: u32) -> Self {
+        self.max_requests_per_second = max_requests_per_second;
+        self
+    }
+
+    /// Caps how many requests are in flight at once, across every clone of
+    /// the client. Zero, the default, means no limit. Only the async client
+    /// applies this; `build_blocking` warns when set.
+    ///
+    /// A request takes an in-flight permit before its
+    /// [`max_requests_per_second`](Self::max_requests_per_second) slot and
+    /// holds it until its body is read, handing it back while it waits to
+    /// retry. So only permit holders queue on the rate limiter: 50 concurrent
+    /// batch tasks with `max_in_flight(5)` and a 5 per second limit keep at
+    /// most five requests waiting, rather than 50 reserving slots ten seconds
+    /// ahead and timing out. Throughput is the lower of the two bounds.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Whether [`find_rxcui`](RxNormClient::find_rxcui) falls back to the top
+    /// approximate match when a name search finds nothing. Off by default.
+    pub fn approximate_fallback(mut self, approximate_fallback: bool) -> Self {
+        self.approximate_fallback = approximate_fallback;
+        self
+    }
+
+    /// Whether a normalized or combined name search that fails or finds
+    /// nothing is tried again as an exact search, for self-hosted RxNav
+    /// images without the index normalized search needs. Errors found before
+    /// sending, and timeouts, are not retried this way.
+    /// [`resolve_rxcui`](RxNormClient::resolve_rxcui) reports such a match
+    /// as [`Resolution::Exact`](crate::Resolution::Exact). Off by default.
+    pub fn fallback_to_exact_on_normalized_failure(mut self, exact_fallback: bool) -> Self {
+        self.exact_fallback = exact_fallback;
+        self
+    }
+
+    /// The lowest approximate match score the fallback accepts. Defaults to
+    /// zero, which accepts any candidate.
+    pub fn min_approximate_score(mut self, min_approximate_score: i32) -> Self {
+        self.min_approximate_score = min_approximate_score;
+        self
+    }
+
+    /// Whether RxCUI searches return their ids sorted ascending without
+    /// duplicates. Off by default, which keeps RxNav's order.
+    pub fn sort_results(mut self, sort_results: bool) -> Self {
+        self.sort_results = sort_results;
+        self
+    }
+
+    /// The most ids an RxCUI search returns, e.g. the first handful for a
+    /// generic term such as `acid`. RxNav cannot page these searches, so the
+    /// full list is still fetched and cut down afterwards, following
+    /// [`sort_results`](Self::sort_results) when that is on. Unlimited by
+    /// default.
+    pub fn max_matches(mut self, max_matches: usize) -> Self {
+        self.max_matches = Some(max_matches);
+        self
+    }
+
+    /// Whether concepts RxNav flags as suppressed (`suppress` = `Y`) are left
+    /// out of results. Related-concept lookups read the flag from their own
+    /// responses, and methods that already fetch properties, such as
+    /// [`find_rxcui_filtered`](RxNormClient::find_rxcui_filtered), check it
+    /// there; [`find_rxcui`](RxNormClient::find_rxcui) needs an extra
+    /// properties request per match. Off by default.
+    pub fn exclude_suppressed(mut self, exclude_suppressed: bool) -> Self {
+        self.exclude_suppressed = exclude_suppressed;
+        self
+    }
+
+    /// The source language to keep, such as `SPA`, for self-hosted RxNav
+    /// mirrors loaded with non-English sources. Related-concept lookups drop
+    /// concepts in other languages and
+    /// [`get_properties`](RxNormClient::get_properties) gives `None` for
+    /// them; concepts whose language RxNav does not give are kept. Requests
+    /// also send it, lowercased, as `Accept-Language` for servers that honour
+    /// it. Every language is kept by default.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// The `User-Agent` header sent with every request. Defaults to
+    /// [`DEFAULT_USER_AGENT`] when the builder creates the HTTP client; a
+    /// client passed to [`http_client`](Self::http_client) keeps its own
+    /// unless this is set.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Receives a callback around every HTTP request, e.g. to export
+    /// metrics. No observer is set by default.
+    pub fn observer(mut self, observer: Arc<dyn RequestObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Which sources RxCUI searches by name or id consider. Defaults to
+    /// [`SourceScope::CurrentOnly`].
+    pub fn source_scope(mut self, source_scope: SourceScope) -> Self {
+        self.source_scope = source_scope;
+        self
+    }
+
+    /// The largest response body read before giving up with
+    /// [`RxNormError::ResponseTooLarge`](crate::RxNormError::ResponseTooLarge).
+    /// Defaults to [`DEFAULT_MAX_RESPONSE_BYTES`]; raise it for bulk endpoints
+    /// such as `allconcepts`. Zero means no limit.
+    pub fn max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// The longest drug name, in characters after whitespace is collapsed,
+    /// that name searches send. Longer names fail with
+    /// [`RxNormError::InvalidInput`](crate::RxNormError::InvalidInput)
+    /// without a request, unless
+    /// [`truncate_long_names`](Self::truncate_long_names) is set. Defaults to
+    /// [`DEFAULT_MAX_NAME_LENGTH`]. Zero means no limit.
+    pub fn max_name_length(mut self, max_name_length: usize) -> Self {
+        self.max_name_length = max_name_length;
+        self
+    }
+
+    /// Whether names over [`max_name_length`](Self::max_name_length) are cut
+    /// to that length, with a warning logged, instead of rejected. Off by
+    /// default.
+    pub fn truncate_long_names(mut self, truncate_long_names: bool) -> Self {
+        self.truncate_long_names = truncate_long_names;
+        self
+    }
+
+    /// A query parameter added to every request, e.g. an API key for a
+    /// gateway in front of a self-hosted RxNav. Can be called repeatedly. An
+    /// endpoint's own parameter of the same name wins.
+    pub fn default_query_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_params.push((key.into(), value.into()));
+        self
+    }
+
+    /// The oldest RxNorm release the server may serve, such as
+    /// `"02-Sep-2024"`. The first request checks `/version.json` and fails
+    /// with [`RxNormError::VersionTooOld`](crate::RxNormError::VersionTooOld)
+    /// if the server is older; see
+    /// [`check_version`](RxNormClient::check_version). Only the async client
+    /// checks this; `build_blocking` warns when set.
+    /// No version is required by default.
+    pub fn require_min_version(mut self, release: impl Into<String>) -> Self {
+        self.min_version = Some(release.into());
+        self
+    }
+
+    /// Creates the configured client.
+    pub fn build(self) -> RxNormClient {
+        let user_agent = match (self.user_agent, &self.http_client) {
+            (Some(user_agent), _) => Some(user_agent),
+            (None, None) => Some(DEFAULT_USER_AGENT.to_string()),
+            (None, Some(_)) => None,
+        };
+        RxNormClient {
+            client: self.http_client.unwrap_or_default(),
+            search_mode: self.search_mode,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            retry_policy: self.retry_policy,
+            timeout: self.timeout,
+            cache: None,
+            rate_limiter: RateLimiter::per_second(self.max_requests_per_second).map(Arc::new),
+            in_flight: InFlightLimit::new(self.max_in_flight).map(Arc::new),
+            throttle: Arc::default(),
+            approximate_fallback: self.approximate_fallback,
+            exact_fallback: self.exact_fallback,
+            min_approximate_score: self.min_approximate_score,
+            sort_results: self.sort_results,
+            max_matches: self.max_matches,
+            exclude_suppressed: self.exclude_suppressed,
+            language: self.language,
+            user_agent,
+            observer: self.observer,
+            source_params: self.source_scope.params(),
+            max_response_bytes: Some(self.max_response_bytes).filter(|&max| max > 0),
+            max_name_length: Some(self.max_name_length).filter(|&max| max > 0),
+            truncate_long_names: self.truncate_long_names,
+            default_params: self.default_params,
+            fixtures: None,
+            min_version: self.min_version,
+            version_checked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Builds a [`RxNormBlockingClient`](crate::RxNormBlockingClient) with this
+    /// configuration. Any async `http_client` set on the builder is not used;
+    /// a default blocking client is created instead. The blocking client
+    /// neither limits in-flight requests nor checks a required version, and
+    /// warns when either is set.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> crate::RxNormBlockingClient {
+        if self.max_in_flight > 0 {
+            log::warn!("max_in_flight is ignored by the blocking client");
+        }
+        if let Some(release) = &self.min_version {
+            log::warn!(
+                "require_min_version({}) is ignored by the blocking client",
+                release
+            );
+        }
+        crate::RxNormBlockingClient {
+            client: reqwest::blocking::Client::new(),
+            search_mode: self.search_mode,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            retry_policy: self.retry_policy,
+            timeout: self.timeout,
+            rate_limiter: RateLimiter::per_second(self.max_requests_per_second).map(Arc::new),
+            throttle: Arc::default(),
+            approximate_fallback: self.approximate_fallback,
+            min_approximate_score: self.min_approximate_score,
+            sort_results: self.sort_results,
+            max_matches: self.max_matches,
+            exclude_suppressed: self.exclude_suppressed,
+            language: self.language,
+            exact_fallback: self.exact_fallback,
+            user_agent: Some(
+                self.user_agent
+                    .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            ),
+            observer: self.observer,
+            source_params: self.source_scope.params(),
+            max_response_bytes: Some(self.max_response_bytes).filter(|&max| max > 0),
+            max_name_length: Some(self.max_name_length).filter(|&max| max > 0),
+            truncate_long_names: self.truncate_long_names,
+            default_params: self.default_params,
+        }
+    }
+}
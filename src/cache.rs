@@ -0,0 +1,449 @@
+use std::hash::Hash;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LockResult, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_json, ConceptGroup, RxNormClient, RxNormError, SearchMode};
+
+/// Key of a cached name search: the lowercased drug string and the search
+/// mode used.
+pub(crate) type CacheKey = (String, SearchMode);
+
+/// The cache key of a name search for an already normalized `drug`. RxNav
+/// matches names regardless of case, so `Aspirin` and `ASPIRIN` share one.
+pub(crate) fn name_key(drug: &str, search_mode: SearchMode) -> CacheKey {
+    (drug.to_lowercase(), search_mode)
+}
+
+/// Key of a cached related-concept lookup: the RxCUI and its sorted term
+/// types, or `None` for every related concept.
+pub(crate) type RelatedKey = (u32, Option<Vec<String>>);
+
+/// A response body and the `ETag` it was served with.
+pub(crate) type TaggedBody = (String, String);
+
+/// A cached value and when it was stored.
+type Stamped<V> = (Instant, V);
+
+/// Hit and miss counters of the client's cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// LRU cache of `find_rxcui` results and related-concept lookups, plus the
+/// display term list, which is fetched at most once, and the bodies of
+/// responses that carried an `ETag`.
+///
+/// Entries older than `ttl` are treated as missing. Responses with an
+/// `ETag` are revalidated on every use, so they never expire.
+pub(crate) struct Cache {
+    entries: Mutex<LruCache<CacheKey, Stamped<Option<Vec<u32>>>>>,
+    related: Mutex<LruCache<RelatedKey, Stamped<Vec<ConceptGroup>>>>,
+    display_terms: Mutex<Option<Stamped<Vec<String>>>>,
+    tagged: Mutex<LruCache<String, TaggedBody>>,
+    ttl: Duration,
+    /// The RxNorm release the entries were fetched from, once known.
+    release: Mutex<Option<String>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Cache {
+    pub(crate) fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            related: Mutex::new(LruCache::new(capacity)),
+            display_terms: Mutex::new(None),
+            tagged: Mutex::new(LruCache::new(capacity)),
+            ttl,
+            release: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<Option<Vec<u32>>> {
+        let found = self.fresh(&mut self.lock(), key);
+        self.count(found.is_some());
+        found
+    }
+
+    pub(crate) fn insert(&self, key: CacheKey, value: Option<Vec<u32>>) {
+        self.lock().put(key, (Instant::now(), value));
+    }
+
+    /// Inserts a name search fetched `age` ago, such as one loaded from a
+    /// file, so it expires when it would have. Entries already expired are
+    /// not inserted.
+    pub(crate) fn insert_aged(&self, key: CacheKey, value: Option<Vec<u32>>, age: Duration) {
+        if age >= self.ttl {
+            return;
+        }
+        if let Some(stored) = Instant::now().checked_sub(age) {
+            self.lock().put(key, (stored, value));
+        }
+    }
+
+    pub(crate) fn related(&self, key: &RelatedKey) -> Option<Vec<ConceptGroup>> {
+        let found = self.fresh(&mut recover(self.related.lock()), key);
+        self.count(found.is_some());
+        found
+    }
+
+    pub(crate) fn insert_related(&self, key: RelatedKey, groups: Vec<ConceptGroup>) {
+        recover(self.related.lock()).put(key, (Instant::now(), groups));
+    }
+
+    pub(crate) fn display_terms(&self) -> Option<Vec<String>> {
+        let found = recover(self.display_terms.lock())
+            .as_ref()
+            .filter(|(stored, _)| self.is_fresh(*stored))
+            .map(|(_, terms)| terms.clone());
+        self.count(found.is_some());
+        found
+    }
+
+    pub(crate) fn set_display_terms(&self, terms: Vec<String>) {
+        *recover(self.display_terms.lock()) = Some((Instant::now(), terms));
+    }
+
+    /// Records that the server serves RxNorm `release`, emptying the cache
+    /// when it was filled from a different one. Returns whether it did.
+    pub(crate) fn observe_release(&self, release: &str) -> bool {
+        let mut known = recover(self.release.lock());
+        let changed = known.as_deref().is_some_and(|known| known != release);
+        if changed {
+            debug!(
+                "RxNorm release changed from {} to {}, clearing the cache",
+                known.as_deref().unwrap_or_default(),
+                release
+            );
+            self.clear();
+        }
+        *known = Some(release.to_string());
+        changed
+    }
+
+    /// Drops every entry, keeping the hit and miss counts.
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+        recover(self.related.lock()).clear();
+        *recover(self.display_terms.lock()) = None;
+        recover(self.tagged.lock()).clear();
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The `ETag` and body last received for `request`, to revalidate with
+    /// `If-None-Match`. Revalidations do not count as hits or misses.
+    pub(crate) fn tagged(&self, request: &str) -> Option<TaggedBody> {
+        recover(self.tagged.lock()).get(request).cloned()
+    }
+
+    pub(crate) fn insert_tagged(&self, request: String, etag: String, body: String) {
+        recover(self.tagged.lock()).put(request, (etag, body));
+    }
+
+    /// The unexpired cached name searches with their ages, least recently
+    /// used first.
+    pub(crate) fn entries(&self) -> Vec<(CacheKey, Option<Vec<u32>>, Duration)> {
+        let mut entries: Vec<_> = self
+            .lock()
+            .iter()
+            .filter(|(_, (stored, _))| self.is_fresh(*stored))
+            .map(|(key, (stored, value))| (key.clone(), value.clone(), stored.elapsed()))
+            .collect();
+        entries.reverse();
+        entries
+    }
+
+    fn count(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The value under `key` in `lru`, dropping it if it has expired.
+    fn fresh<K: Hash + Eq, V: Clone>(
+        &self,
+        lru: &mut LruCache<K, Stamped<V>>,
+        key: &K,
+    ) -> Option<V> {
+        let (stored, value) = lru.get(key)?;
+        if self.is_fresh(*stored) {
+            return Some(value.clone());
+        }
+        lru.pop(key);
+        None
+    }
+
+    fn is_fresh(&self, stored: Instant) -> bool {
+        stored.elapsed() < self.ttl
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LruCache<CacheKey, Stamped<Option<Vec<u32>>>>> {
+        recover(self.entries.lock())
+    }
+}
+
+/// A panic while holding a lock cannot leave the cache half-updated in a way
+/// that matters, so recover from poisoning.
+fn recover<T>(result: LockResult<MutexGuard<'_, T>>) -> MutexGuard<'_, T> {
+    result.unwrap_or_else(|e| e.into_inner())
+}
+
+/// The JSON layout of a saved cache. The RxNorm release is stored so that a
+/// cache written against an older release is not loaded.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: String,
+    entries: Vec<CacheFileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFileEntry {
+    term: String,
+    search_mode: SearchMode,
+    rxcuis: Option<Vec<u32>>,
+    /// When the entry was fetched, in seconds since the Unix epoch, so a
+    /// loaded entry keeps its age. Files without it load nothing.
+    #[serde(default)]
+    fetched_at: u64,
+}
+
+/// Seconds since the Unix epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+impl RxNormClient {
+    /// Writes the cached name searches to `path` as JSON, stamped with the
+    /// server's current RxNorm release, which costs one `version` request.
+    /// Each entry keeps when it was fetched, so reloading does not extend
+    /// its time to live.
+    pub async fn save_cache_to_path(&self, path: impl AsRef<Path>) -> Result<(), RxNormError> {
+        let version = self.get_version().await?.version;
+        let entries = self
+            .cache
+            .as_deref()
+            .map(Cache::entries)
+            .unwrap_or_default();
+        let now = unix_now();
+        let file = CacheFile {
+            version,
+            entries: entries
+                .into_iter()
+                .map(|((term, search_mode), rxcuis, age)| CacheFileEntry {
+                    term,
+                    search_mode,
+                    rxcuis,
+                    fetched_at: now.saturating_sub(age.as_secs()),
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        tokio::fs::write(path, json).await?;
+        Ok(())
+    }
+
+    /// Like [`with_cache`](Self::with_cache), but fills the cache from a file
+    /// written by [`save_cache_to_path`](Self::save_cache_to_path).
+    ///
+    /// The file is ignored when it does not exist, cannot be parsed, or was
+    /// saved against a different RxNorm release than the server now reports.
+    /// Entries older than the cache's time to live are left out.
+    pub async fn with_cache_from_path(
+        self,
+        capacity: usize,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, RxNormError> {
+        let rx_client = self.with_cache(capacity);
+        let cache = match &rx_client.cache {
+            Some(cache) => cache,
+            None => return Ok(rx_client),
+        };
+        let json = match tokio::fs::read_to_string(path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(rx_client),
+            Err(e) => return Err(e.into()),
+        };
+        let file: CacheFile = match parse_json(&json) {
+            Ok(file) => file,
+            Err(e) => {
+                debug!("Ignoring unreadable cache file: {}", e);
+                return Ok(rx_client);
+            }
+        };
+        let version = rx_client.get_version().await?.version;
+        if file.version != version {
+            debug!(
+                "Ignoring cache saved for RxNorm {}, server has {}",
+                file.version, version
+            );
+            return Ok(rx_client);
+        }
+        let now = unix_now();
+        for entry in file.entries {
+            let age = Duration::from_secs(now.saturating_sub(entry.fetched_at));
+            cache.insert_aged(name_key(&entry.term, entry.search_mode), entry.rxcuis, age);
+        }
+        Ok(rx_client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(drug: &str) -> CacheKey {
+        (drug.to_string(), SearchMode::ExactThenNormalized)
+    }
+
+    fn cache(capacity: usize) -> Cache {
+        Cache::new(
+            NonZeroUsize::new(capacity).unwrap(),
+            crate::DEFAULT_CACHE_TTL,
+        )
+    }
+
+    #[test]
+    fn test_cache_counts_hits_and_misses() {
+        let cache = cache(2);
+        assert_eq!(None, cache.get(&key("aspirin")));
+        cache.insert(key("aspirin"), Some(vec![1191]));
+        cache.insert(key("unknown"), None);
+        assert_eq!(Some(Some(vec![1191])), cache.get(&key("aspirin")));
+        assert_eq!(Some(None), cache.get(&key("unknown")));
+        assert_eq!(CacheStats { hits: 2, misses: 1 }, cache.stats());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = cache(1);
+        cache.insert(key("aspirin"), Some(vec![1191]));
+        cache.insert(key("vit-c"), Some(vec![1151]));
+        assert_eq!(None, cache.get(&key("aspirin")));
+    }
+
+    #[test]
+    fn test_expired_entries_are_misses() {
+        let cache = Cache::new(NonZeroUsize::new(2).unwrap(), Duration::ZERO);
+        cache.insert(key("aspirin"), Some(vec![1191]));
+        cache.set_display_terms(vec![String::from("aspirin")]);
+        assert_eq!(None, cache.get(&key("aspirin")));
+        assert_eq!(None, cache.display_terms());
+        assert!(cache.entries().is_empty());
+        assert_eq!(CacheStats { hits: 0, misses: 2 }, cache.stats());
+    }
+
+    #[test]
+    fn test_aged_entries_keep_their_expiry() {
+        let cache = Cache::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(3600));
+        cache.insert_aged(key("aspirin"), Some(vec![1191]), Duration::from_secs(7200));
+        cache.insert_aged(key("vit-c"), Some(vec![1151]), Duration::from_secs(1800));
+        assert_eq!(None, cache.get(&key("aspirin")));
+        assert_eq!(Some(Some(vec![1151])), cache.get(&key("vit-c")));
+        let age = cache.entries()[0].2;
+        assert!(age >= Duration::from_secs(1800) && age < Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_new_release_clears_cache() {
+        let cache = cache(2);
+        assert!(!cache.observe_release("02-Sep-2024"));
+        cache.insert(key("aspirin"), Some(vec![1191]));
+        assert!(!cache.observe_release("02-Sep-2024"));
+        assert_eq!(Some(Some(vec![1191])), cache.get(&key("aspirin")));
+        assert!(cache.observe_release("07-Oct-2024"));
+        assert_eq!(None, cache.get(&key("aspirin")));
+    }
+
+    #[test]
+    fn test_cache_keeps_display_terms() {
+        let cache = cache(1);
+        assert_eq!(None, cache.display_terms());
+        cache.set_display_terms(vec![String::from("aspirin")]);
+        assert_eq!(Some(vec![String::from("aspirin")]), cache.display_terms());
+        assert_eq!(CacheStats { hits: 1, misses: 1 }, cache.stats());
+    }
+
+    #[test]
+    fn test_entries_are_least_recent_first() {
+        let cache = cache(2);
+        cache.insert(key("aspirin"), Some(vec![1191]));
+        cache.insert(key("vit-c"), Some(vec![1151]));
+        let terms: Vec<String> = cache
+            .entries()
+            .into_iter()
+            .map(|((term, _), _, _)| term)
+            .collect();
+        assert_eq!(vec!["aspirin", "vit-c"], terms);
+    }
+
+    #[tokio::test]
+    async fn test_cache_round_trips_through_file() {
+        use crate::test_server::{response, serve};
+
+        let version = r#"{"version":"07-Oct-2024","apiVersion":"3.1.256"}"#;
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+            response("200 OK", &[], version),
+            response("200 OK", &[], version),
+        ])
+        .await;
+        let base_url = format!("http://{}/REST", addr);
+        let path = std::env::temp_dir().join(format!("rxnormalizer-cache-{}.json", addr.port()));
+
+        let rx_client = RxNormClient::builder()
+            .base_url(&base_url)
+            .build()
+            .with_cache(8);
+        rx_client.find_rxcui("vit-c").await.unwrap();
+        rx_client.save_cache_to_path(&path).await.unwrap();
+
+        let reloaded = RxNormClient::builder()
+            .base_url(&base_url)
+            .build()
+            .with_cache_from_path(8, &path)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            Some(vec![1151]),
+            reloaded.find_rxcui("vit-c").await.unwrap()
+        );
+        assert_eq!(1, reloaded.cache_stats().hits);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_cache_file_is_ignored() {
+        let path = std::env::temp_dir().join("rxnormalizer-cache-corrupt.json");
+        std::fs::write(&path, "not a cache file").unwrap();
+
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build()
+            .with_cache_from_path(8, &path)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(0, rx_client.cache_stats().hits);
+    }
+}
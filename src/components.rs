@@ -0,0 +1,102 @@
+use futures::future::try_join_all;
+
+use crate::{RxNormClient, RxNormError};
+
+/// Words dropped as strength units when splitting a combination string.
+const STRENGTH_UNITS: &[&str] = &[
+    "mg", "mcg", "µg", "g", "kg", "ml", "l", "meq", "mmol", "unit", "units", "iu", "%",
+];
+
+/// Shortest part a hyphen may separate for the hyphen to split components,
+/// so names such as `vit-c` and `co-trimoxazole` stay whole.
+const MIN_HYPHENATED_PART: usize = 4;
+
+impl RxNormClient {
+    /// Splits a combination drug string such as `"amlodipine/benazepril 5-20 mg"`
+    /// into its components and looks each one up with
+    /// [`find_rxcui`](Self::find_rxcui), returning every component with its
+    /// matches in the order it appeared.
+    ///
+    /// Strengths are removed first; the rest is split on `/` and `+`, and on
+    /// `-` between words of at least four letters, so `vit-c` is one name.
+    /// A string with nothing left after removing strengths gives an empty vec.
+    pub async fn find_rxcui_components(
+        &self,
+        drug: &str,
+    ) -> Result<Vec<(String, Option<Vec<u32>>)>, RxNormError> {
+        let drug = self.prepare_term(drug)?;
+        try_join_all(
+            split_components(&drug)
+                .into_iter()
+                .map(|component| async move {
+                    let rxcuis = self.find_rxcui(&component).await?;
+                    Ok((component, rxcuis))
+                }),
+        )
+        .await
+    }
+}
+
+/// The components of a combination drug string, with strengths left out.
+pub(crate) fn split_components(drug: &str) -> Vec<String> {
+    let words: Vec<&str> = drug
+        .split_whitespace()
+        .filter(|word| !is_strength(word))
+        .collect();
+    words
+        .join(" ")
+        .split(['/', '+'])
+        .flat_map(split_hyphenated)
+        .map(str::trim)
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `part` split on `-` when every piece is long enough to be a name of its
+/// own, or `part` whole otherwise.
+fn split_hyphenated(part: &str) -> Vec<&str> {
+    let pieces: Vec<&str> = part.split('-').collect();
+    if pieces
+        .iter()
+        .all(|piece| piece.trim().chars().count() >= MIN_HYPHENATED_PART)
+    {
+        pieces
+    } else {
+        vec![part]
+    }
+}
+
+/// Whether `word` is a strength such as `5-20`, `10mg` or `mg/ml`.
+fn is_strength(word: &str) -> bool {
+    if word.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return true;
+    }
+    word.to_lowercase()
+        .split('/')
+        .all(|unit| STRENGTH_UNITS.contains(&unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_components_strips_strengths() {
+        assert_eq!(
+            vec!["amlodipine", "benazepril"],
+            split_components("amlodipine/benazepril 5-20 mg")
+        );
+        assert_eq!(
+            vec!["hydrochlorothiazide", "lisinopril"],
+            split_components("hydrochlorothiazide-lisinopril 12.5mg/10mg")
+        );
+        assert_eq!(
+            vec!["sodium chloride", "potassium chloride"],
+            split_components("sodium chloride + potassium chloride 20 mEq/L")
+        );
+        assert_eq!(vec!["vit-c"], split_components("vit-c 500 mg"));
+        assert_eq!(vec!["co-trimoxazole"], split_components("co-trimoxazole"));
+        assert!(split_components("500 mg").is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+use crate::related::RawConcept;
+use crate::{
+    join_list, parse_json, ConceptStatus, RelatedConcept, RxNormClient, RxNormError, TermType,
+};
+
+impl RxNormClient {
+    /// Fetches every concept with one of the given term types, e.g. all
+    /// ingredients with `&[TermType::In, TermType::Pin]`.
+    ///
+    /// The response can hold tens of thousands of concepts; it is
+    /// deserialized straight into the returned vector.
+    pub async fn get_all_concepts(
+        &self,
+        ttys: &[TermType],
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let url = self.url("/allconcepts.json");
+        let tty = join_list(ttys);
+        let body = self.get(&url, &[("tty", &tty)]).await?;
+        parse_min_concepts(&body)
+    }
+
+    /// Fetches every concept that currently has `status`, e.g. all obsolete
+    /// concepts. Comparing the result with one from a previous release gives
+    /// the concepts whose status changed.
+    pub async fn get_concepts_by_status(
+        &self,
+        status: ConceptStatus,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let url = self.url("/allstatus.json");
+        let body = self.get(&url, &[("status", status.as_str())]).await?;
+        parse_min_concepts(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct MinConceptResponse {
+    #[serde(rename = "minConceptGroup")]
+    min_concept_group: Option<MinConceptGroup>,
+}
+
+#[derive(Deserialize)]
+struct MinConceptGroup {
+    #[serde(rename = "minConcept", default)]
+    min_concept: Vec<RawConcept>,
+}
+
+/// Parses the `minConceptGroup` list shared by `getAllConceptsByTTY` and
+/// `getAllConceptsByStatus`.
+pub(crate) fn parse_min_concepts(body: &str) -> Result<Vec<RelatedConcept>, RxNormError> {
+    let response: MinConceptResponse = parse_json(body)?;
+    response
+        .min_concept_group
+        .map(|group| group.min_concept)
+        .unwrap_or_default()
+        .into_iter()
+        .map(RawConcept::into_concept)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_min_concepts() {
+        let body = r#"{"minConceptGroup":{"minConcept":[
+            {"rxcui":"161","name":"acetaminophen","tty":"IN"},
+            {"rxcui":"1151","name":"ascorbic acid","tty":"IN"}]}}"#;
+        let concepts = parse_min_concepts(body).unwrap();
+        assert_eq!(2, concepts.len());
+        assert_eq!(1151, concepts[1].rxcui);
+        assert_eq!("acetaminophen", concepts[0].name);
+    }
+
+    #[test]
+    fn test_parse_no_min_concepts() {
+        assert!(parse_min_concepts(r#"{"minConceptGroup":null}"#)
+            .unwrap()
+            .is_empty());
+    }
+}
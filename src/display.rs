@@ -0,0 +1,73 @@
+use serde::Deserialize;
+
+use crate::{parse_json, RxNormClient, RxNormError};
+
+impl RxNormClient {
+    /// Fetches every display term RxNav knows, for building a local
+    /// autocomplete index.
+    ///
+    /// The list is large. When the client has a cache (see
+    /// [`with_cache`](Self::with_cache)) it is only downloaded once.
+    pub async fn get_display_terms(&self) -> Result<Vec<String>, RxNormError> {
+        if let Some(terms) = self.cache.as_ref().and_then(|cache| cache.display_terms()) {
+            return Ok(terms);
+        }
+        let url = self.url("/displaynames.json");
+        let body = self.get(&url, &[]).await?;
+        let terms = parse_display_terms(&body)?;
+        if let Some(cache) = &self.cache {
+            cache.set_display_terms(terms.clone());
+        }
+        Ok(terms)
+    }
+}
+
+#[derive(Deserialize)]
+struct DisplayTermsResponse {
+    #[serde(rename = "displayTermsList")]
+    display_terms_list: Option<DisplayTermsList>,
+}
+
+#[derive(Deserialize)]
+struct DisplayTermsList {
+    #[serde(default)]
+    term: Vec<String>,
+}
+
+pub(crate) fn parse_display_terms(body: &str) -> Result<Vec<String>, RxNormError> {
+    let response: DisplayTermsResponse = parse_json(body)?;
+    Ok(response
+        .display_terms_list
+        .map(|list| list.term)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    #[test]
+    fn test_parse_display_terms() {
+        let body = r#"{"displayTermsList":{"term":["acetaminophen","aspirin"]}}"#;
+        assert_eq!(
+            vec!["acetaminophen", "aspirin"],
+            parse_display_terms(body).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_display_terms_are_fetched_once_with_cache() {
+        let body = r#"{"displayTermsList":{"term":["aspirin"]}}"#;
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .build()
+            .with_cache(1);
+        let first = rx_client.get_display_terms().await.unwrap();
+        let second = rx_client.get_display_terms().await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(1, rx_client.cache_stats().hits);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+/// The parts of an RxNorm clinical or branded drug name, as split by
+/// [`parse_drug_components`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrugComponents {
+    /// The ingredient, e.g. `acetaminophen`.
+    pub ingredient: Option<String>,
+    pub strength: Option<Strength>,
+    /// The dose form, e.g. `Oral Tablet`.
+    pub dose_form: Option<String>,
+}
+
+/// A strength such as `325 MG` or `0.5 MG/ML`, kept as RxNorm writes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Strength {
+    /// The amount, e.g. `325`.
+    pub value: String,
+    /// The unit, e.g. `MG`.
+    pub unit: String,
+}
+
+/// Splits an RxNorm drug name such as `"acetaminophen 325 MG Oral Tablet"`
+/// into ingredient, strength and dose form, following RxNorm's naming
+/// grammar `<ingredient> <value> <unit> <dose form> [<brand>]`.
+///
+/// Parts that cannot be read from the name are `None`, never guessed. A
+/// multi-ingredient name (`"amlodipine 5 MG / benazepril 10 MG Oral Capsule"`)
+/// has no single ingredient or strength, so only its dose form is given.
+pub fn parse_drug_components(name: &str) -> DrugComponents {
+    let name = name.trim();
+    let name = match name.rfind(" [") {
+        Some(brand) if name.ends_with(']') => &name[..brand],
+        _ => name,
+    };
+    let segments: Vec<&str> = name.split(" / ").collect();
+    let last = match parse_segment(segments[segments.len() - 1]) {
+        Some(last) => last,
+        None => return DrugComponents::default(),
+    };
+    if segments.len() > 1 {
+        return DrugComponents {
+            dose_form: last.dose_form,
+            ..DrugComponents::default()
+        };
+    }
+    last
+}
+
+/// Parses one `<ingredient> <value> <unit> <rest>` segment of a drug name.
+fn parse_segment(segment: &str) -> Option<DrugComponents> {
+    let words: Vec<&str> = segment.split_whitespace().collect();
+    let at = words.iter().position(|word| is_amount(word))?;
+    let unit = words.get(at + 1).filter(|word| is_unit(word))?;
+    if at == 0 {
+        return None;
+    }
+    let dose_form = words[at + 2..].join(" ");
+    Some(DrugComponents {
+        ingredient: Some(words[..at].join(" ")),
+        strength: Some(Strength {
+            value: words[at].to_string(),
+            unit: unit.to_string(),
+        }),
+        dose_form: Some(dose_form).filter(|form| !form.is_empty()),
+    })
+}
+
+/// Whether `word` is a number such as `325`, `0.5` or `1,000`.
+fn is_amount(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+}
+
+/// Whether `word` is an RxNorm unit such as `MG`, `UNT/ML` or `%`.
+fn is_unit(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase() || c == '%')
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '/' || c == '%')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strength(value: &str, unit: &str) -> Option<Strength> {
+        Some(Strength {
+            value: value.to_string(),
+            unit: unit.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parse_clinical_drug() {
+        let components = parse_drug_components("acetaminophen 325 MG Oral Tablet");
+        assert_eq!(Some(String::from("acetaminophen")), components.ingredient);
+        assert_eq!(strength("325", "MG"), components.strength);
+        assert_eq!(Some(String::from("Oral Tablet")), components.dose_form);
+    }
+
+    #[test]
+    fn test_parse_branded_drug_and_compound_unit() {
+        let components =
+            parse_drug_components("insulin glargine 100 UNT/ML Injectable Solution [Lantus]");
+        assert_eq!(
+            Some(String::from("insulin glargine")),
+            components.ingredient
+        );
+        assert_eq!(strength("100", "UNT/ML"), components.strength);
+        assert_eq!(
+            Some(String::from("Injectable Solution")),
+            components.dose_form
+        );
+    }
+
+    #[test]
+    fn test_parse_component_without_dose_form() {
+        let components = parse_drug_components("amoxicillin 0.5 MG/ML");
+        assert_eq!(strength("0.5", "MG/ML"), components.strength);
+        assert_eq!(None, components.dose_form);
+    }
+
+    #[test]
+    fn test_multi_ingredient_keeps_only_dose_form() {
+        let components =
+            parse_drug_components("amlodipine 5 MG / benazepril hydrochloride 10 MG Oral Capsule");
+        assert_eq!(None, components.ingredient);
+        assert_eq!(None, components.strength);
+        assert_eq!(Some(String::from("Oral Capsule")), components.dose_form);
+    }
+
+    #[test]
+    fn test_names_outside_the_grammar_give_nothing() {
+        assert_eq!(
+            DrugComponents::default(),
+            parse_drug_components("acetaminophen")
+        );
+        assert_eq!(DrugComponents::default(), parse_drug_components("Tylenol"));
+        assert_eq!(
+            DrugComponents::default(),
+            parse_drug_components("12 HR pseudoephedrine Extended Release Oral Tablet")
+        );
+        assert_eq!(
+            DrugComponents::default(),
+            parse_drug_components("vitamin c 500 mg")
+        );
+    }
+}
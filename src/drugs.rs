@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Deserialize;
+
+use crate::related::{into_concept_groups, RawConcept, RawConceptGroup};
+use crate::{
+    join_list, parse_drug_components, parse_json, ConceptGroup, RelatedConcept, RxNormClient,
+    RxNormError,
+};
+
+/// Branded product requests kept in flight by
+/// [`brand_to_clinical_drugs`](RxNormClient::brand_to_clinical_drugs).
+const BRAND_CONCURRENCY: usize = 4;
+
+impl RxNormClient {
+    /// Searches for the branded and generic drugs matching `name`, grouped by
+    /// term type (SBD, SCD, BPCK, ...).
+    ///
+    /// Term types without matches are left out, so a name that matches nothing
+    /// gives an empty vector.
+    pub async fn get_drugs(&self, name: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let url = self.url("/drugs.json");
+        let body = self.get(&url, &[("name", name)]).await?;
+        parse_drug_group(&body)
+    }
+
+    /// Like [`get_drugs`](Self::get_drugs), but keeps only products with the
+    /// given dose form (e.g. `"Oral Tablet"`) and route (e.g. `"Oral"`),
+    /// compared case-insensitively. `None` leaves that filter off.
+    ///
+    /// RxNav cannot filter `/drugs.json` itself, so the dose form is read from
+    /// each concept's name with [`parse_drug_components`]; this needs no
+    /// extra requests. Concepts whose names carry no dose form, such as
+    /// multi-ingredient packs, are dropped whenever a filter is set.
+    pub async fn get_drugs_filtered(
+        &self,
+        name: &str,
+        dose_form: Option<&str>,
+        route: Option<&str>,
+    ) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let groups = self.get_drugs(name).await?;
+        Ok(filter_drugs(groups, dose_form, route))
+    }
+
+    /// Finds the brand names (`BN`) whose products contain exactly the given
+    /// ingredients, e.g. the ingredient RxCUIs for amlodipine and benazepril.
+    ///
+    /// An empty list gives an empty vec without sending a request.
+    pub async fn get_brands_for_ingredients(
+        &self,
+        ingredient_rxcuis: &[u32],
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        if ingredient_rxcuis.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = self.url("/brands.json");
+        let ids = join_list(ingredient_rxcuis);
+        let body = self.get(&url, &[("ingredientids", &ids)]).await?;
+        parse_brand_group(&body)
+    }
+
+    /// Expands a brand name (`BN`) such as Tylenol into the clinical drugs
+    /// (`SCD`) and generic packs (`GPCK`) sold under it.
+    ///
+    /// The brand's products (`SBD`, `BPCK`) are fetched first and each one is
+    /// followed along `tradename_of` to its generic form, so this costs one
+    /// request per product. Concepts are returned once each, in the order
+    /// they were first reached; a brand without products gives an empty vec.
+    pub async fn brand_to_clinical_drugs(
+        &self,
+        brand_rxcui: u32,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let products: Vec<RelatedConcept> = self
+            .get_related_by_type(brand_rxcui, &["SBD", "BPCK"])
+            .await?
+            .into_iter()
+            .flat_map(|group| group.concepts)
+            .collect();
+        let generics: Vec<Vec<RelatedConcept>> = stream::iter(products)
+            .map(|product| async move {
+                self.get_related_by_rela(product.rxcui, &["tradename_of"])
+                    .await
+            })
+            .buffered(BRAND_CONCURRENCY)
+            .try_collect()
+            .await?;
+        let mut seen = HashSet::new();
+        Ok(generics
+            .into_iter()
+            .flatten()
+            .filter(|concept| seen.insert(concept.rxcui))
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct DrugsResponse {
+    #[serde(rename = "drugGroup", default)]
+    drug_group: DrugGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct DrugGroup {
+    #[serde(rename = "conceptGroup", default)]
+    concept_group: Vec<RawConceptGroup>,
+}
+
+/// The concepts in `groups` matching the dose form and route filters.
+pub(crate) fn filter_drugs(
+    groups: Vec<ConceptGroup>,
+    dose_form: Option<&str>,
+    route: Option<&str>,
+) -> Vec<ConceptGroup> {
+    if dose_form.is_none() && route.is_none() {
+        return groups;
+    }
+    let matches = |name: &str| {
+        let form = match parse_drug_components(name).dose_form {
+            Some(form) => form.to_lowercase(),
+            None => return false,
+        };
+        let form_matches = dose_form.is_none_or(|wanted| form == wanted.to_lowercase());
+        let route_matches = route.is_none_or(|route| {
+            format!(" {} ", form).contains(&format!(" {} ", route.to_lowercase()))
+        });
+        form_matches && route_matches
+    };
+    groups
+        .into_iter()
+        .map(|mut group| {
+            group.concepts.retain(|concept| matches(&concept.name));
+            group
+        })
+        .filter(|group| !group.concepts.is_empty())
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct BrandsResponse {
+    #[serde(rename = "brandGroup", default)]
+    brand_group: BrandGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct BrandGroup {
+    #[serde(rename = "conceptProperties", default)]
+    concept_properties: Vec<RawConcept>,
+}
+
+pub(crate) fn parse_brand_group(body: &str) -> Result<Vec<RelatedConcept>, RxNormError> {
+    let response: BrandsResponse = parse_json(body)?;
+    response
+        .brand_group
+        .concept_properties
+        .into_iter()
+        .map(RawConcept::into_concept)
+        .collect()
+}
+
+pub(crate) fn parse_drug_group(body: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+    let response: DrugsResponse = parse_json(body)?;
+    let mut groups = into_concept_groups(response.drug_group.concept_group)?;
+    groups.retain(|group| !group.concepts.is_empty());
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve_recording};
+
+    #[test]
+    fn test_parse_drug_group() {
+        let body = r#"{"drugGroup":{"name":null,"conceptGroup":[{"tty":"BPCK"},
+            {"tty":"SBD","conceptProperties":[{"rxcui":"209387","name":"Tylenol 325 MG Oral Tablet","tty":"SBD"}]}]}}"#;
+        let groups = parse_drug_group(body).unwrap();
+        assert_eq!(1, groups.len());
+        assert_eq!(209387, groups[0].concepts[0].rxcui);
+    }
+
+    #[test]
+    fn test_parse_no_drugs() {
+        let body = r#"{"drugGroup":{"name":"qqq"}}"#;
+        assert!(parse_drug_group(body).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_brand_group() {
+        let body = r#"{"brandGroup":{"ingredientList":{"rxnormId":["17767","18867"]},
+            "conceptProperties":[{"rxcui":"219266","name":"Lotrel","synonym":"","tty":"BN"}]}}"#;
+        let brands = parse_brand_group(body).unwrap();
+        assert_eq!(1, brands.len());
+        assert_eq!("Lotrel", brands[0].name);
+        assert!(parse_brand_group(r#"{"brandGroup":{}}"#)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_filter_drugs_by_dose_form_and_route() {
+        let body = r#"{"drugGroup":{"conceptGroup":[
+            {"tty":"SCD","conceptProperties":[
+                {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"},
+                {"rxcui":"1148399","name":"acetaminophen 10 MG/ML Injectable Solution","tty":"SCD"},
+                {"rxcui":"1148400","name":"24 HR acetaminophen 650 MG Extended Release Oral Tablet","tty":"SCD"}]},
+            {"tty":"SBD","conceptProperties":[
+                {"rxcui":"209387","name":"acetaminophen 325 MG Oral Tablet [Tylenol]","tty":"SBD"}]}]}}"#;
+        let groups = parse_drug_group(body).unwrap();
+        let ids = |groups: Vec<ConceptGroup>| -> Vec<u32> {
+            groups
+                .iter()
+                .flat_map(|group| group.concepts.iter().map(|c| c.rxcui))
+                .collect()
+        };
+        let tablets = filter_drugs(groups.clone(), Some("oral tablet"), None);
+        assert_eq!(vec![313782, 209387], ids(tablets));
+        let injectable = filter_drugs(groups.clone(), None, Some("Injectable"));
+        assert_eq!(vec![1148399], ids(injectable));
+        assert_eq!(4, ids(filter_drugs(groups, None, None)).len());
+    }
+
+    #[tokio::test]
+    async fn test_brand_to_clinical_drugs_follows_each_product() {
+        let (addr, requests) = serve_recording(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[
+                    {"tty":"SBD","conceptProperties":[
+                        {"rxcui":"209387","name":"acetaminophen 325 MG Oral Tablet [Tylenol]","tty":"SBD"},
+                        {"rxcui":"209459","name":"acetaminophen 500 MG Oral Tablet [Tylenol]","tty":"SBD"}]},
+                    {"tty":"BPCK"}]}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"SCD","conceptProperties":[
+                    {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"}]}]}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"SCD","conceptProperties":[
+                    {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"},
+                    {"rxcui":"198440","name":"acetaminophen 500 MG Oral Tablet","tty":"SCD"}]}]}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let ids: Vec<u32> = rx_client
+            .brand_to_clinical_drugs(202433)
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![313782, 198440], ids);
+        assert_eq!(
+            "GET /rxcui/202433/related.json?tty=SBD+BPCK HTTP/1.1",
+            requests.recv().unwrap()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::{RxNormClient, RxNormClientBuilder, RxNormError};
+
+/// RxNav REST root, e.g. `http://localhost:4000/REST`.
+const BASE_URL_VAR: &str = "RXNAV_BASE_URL";
+/// Per-request timeout in milliseconds.
+const TIMEOUT_MS_VAR: &str = "RXNAV_TIMEOUT_MS";
+/// Retries after a failed request.
+const MAX_RETRIES_VAR: &str = "RXNAV_MAX_RETRIES";
+/// Requests started per second.
+const RPS_VAR: &str = "RXNAV_RPS";
+
+impl RxNormClient {
+    /// Creates a client configured from the environment:
+    ///
+    /// - `RXNAV_BASE_URL`: the REST root, such as a self-hosted RxNav-in-a-Box
+    /// - `RXNAV_TIMEOUT_MS`: the per-request timeout in milliseconds
+    /// - `RXNAV_MAX_RETRIES`: retries after a failed request
+    /// - `RXNAV_RPS`: the most requests started per second
+    ///
+    /// Unset or empty variables keep the builder defaults. A value that is
+    /// not valid Unicode or not a number gives [`RxNormError::Config`].
+    pub fn from_env() -> Result<Self, RxNormError> {
+        let builder = builder_from(|name| match env::var(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(e) => Err(RxNormError::Config(format!("{}: {}", name, e))),
+        })?;
+        Ok(builder.build())
+    }
+}
+
+/// A builder configured from the variables `lookup` returns.
+fn builder_from<F>(lookup: F) -> Result<RxNormClientBuilder, RxNormError>
+where
+    F: Fn(&str) -> Result<Option<String>, RxNormError>,
+{
+    let var = |name: &str| -> Result<Option<String>, RxNormError> {
+        Ok(lookup(name)?
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()))
+    };
+    let mut builder = RxNormClient::builder();
+    if let Some(base_url) = var(BASE_URL_VAR)? {
+        builder = builder.base_url(base_url);
+    }
+    if let Some(timeout_ms) = parse_var(TIMEOUT_MS_VAR, var(TIMEOUT_MS_VAR)?)? {
+        builder = builder.timeout(Duration::from_millis(timeout_ms));
+    }
+    if let Some(max_retries) = parse_var(MAX_RETRIES_VAR, var(MAX_RETRIES_VAR)?)? {
+        builder = builder.max_retries(max_retries);
+    }
+    if let Some(rps) = parse_var(RPS_VAR, var(RPS_VAR)?)? {
+        builder = builder.max_requests_per_second(rps);
+    }
+    Ok(builder)
+}
+
+fn parse_var<T: FromStr>(name: &str, value: Option<String>) -> Result<Option<T>, RxNormError> {
+    value
+        .map(|value| {
+            value.parse().map_err(|_| {
+                RxNormError::Config(format!("{} must be a whole number, got '{}'", name, value))
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Result<Option<String>, RxNormError> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| Ok(vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_builder_from_vars() {
+        let rx_client = builder_from(vars(&[
+            ("RXNAV_BASE_URL", "http://localhost:4000/REST/"),
+            ("RXNAV_TIMEOUT_MS", "1500"),
+            ("RXNAV_MAX_RETRIES", ""),
+        ]))
+        .unwrap()
+        .build();
+        assert_eq!("http://localhost:4000/REST", rx_client.base_url);
+        assert_eq!(Some(Duration::from_millis(1500)), rx_client.timeout);
+        assert_eq!(
+            crate::DEFAULT_MAX_RETRIES,
+            rx_client.retry_policy.max_retries
+        );
+    }
+
+    #[test]
+    fn test_malformed_var_is_a_config_error() {
+        let result = builder_from(vars(&[("RXNAV_RPS", "fast")]));
+        assert!(matches!(result, Err(RxNormError::Config(msg)) if msg.contains("RXNAV_RPS")));
+    }
+}
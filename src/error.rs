@@ -0,0 +1,142 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+
+/// Errors returned by the RxNorm client.
+#[derive(Debug)]
+pub enum RxNormError {
+    /// The request could not be sent or the response could not be read.
+    Http(reqwest::Error),
+    /// The request did not complete within the configured timeout.
+    Timeout,
+    /// RxNav answered with a non-success HTTP status.
+    UnexpectedStatus(StatusCode),
+    /// The response body was not the JSON we expected.
+    JsonParse(String),
+    /// RxNav answered with a success status but not with JSON, such as the
+    /// HTML page it serves during maintenance. `snippet` is the start of the
+    /// body.
+    UnexpectedContentType {
+        content_type: Option<String>,
+        snippet: String,
+    },
+    /// An id in the response could not be parsed as an RxCUI.
+    IdParse(String),
+    /// The input was rejected before any request was sent.
+    InvalidInput(String),
+    /// An offline client made with
+    /// [`with_fixtures`](crate::RxNormClient::with_fixtures) has no fixture
+    /// for this drug name or request.
+    NoFixture(String),
+    /// The server's RxNorm release is older than the one required with
+    /// [`require_min_version`](crate::RxNormClientBuilder::require_min_version).
+    VersionTooOld { found: String, required: String },
+    /// The client configuration, e.g. from the environment, was invalid.
+    Config(String),
+    /// RxNav answered with a success status but reported an error in the
+    /// JSON body.
+    ApiError { message: String },
+    /// The response body was larger than the configured
+    /// `max_response_bytes`, so reading it was abandoned.
+    ResponseTooLarge { limit: usize },
+    /// [`assert_all_active`](crate::RxNormClient::assert_all_active) found
+    /// these RxCUIs retired, remapped or otherwise not active.
+    InactiveConcepts(Vec<u32>),
+    /// Reading or writing a local file, such as a saved cache, failed.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for RxNormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RxNormError::Http(e) => write!(f, "HTTP error: {}", e),
+            RxNormError::Timeout => write!(f, "request to RxNav timed out"),
+            RxNormError::UnexpectedStatus(status) => {
+                write!(f, "RxNav returned an unexpected status: {}", status)
+            }
+            RxNormError::JsonParse(msg) => write!(f, "could not parse RxNav response: {}", msg),
+            RxNormError::UnexpectedContentType {
+                content_type,
+                snippet,
+            } => write!(
+                f,
+                "RxNav answered with {} instead of JSON: {}",
+                content_type.as_deref().unwrap_or("no content type"),
+                snippet
+            ),
+            RxNormError::IdParse(id) => write!(f, "could not parse RxCUI '{}'", id),
+            RxNormError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+            RxNormError::NoFixture(what) => write!(f, "no fixture for '{}'", what),
+            RxNormError::VersionTooOld { found, required } => write!(
+                f,
+                "RxNav serves RxNorm release {}, but {} or later is required",
+                found, required
+            ),
+            RxNormError::Config(msg) => write!(f, "invalid configuration: {}", msg),
+            RxNormError::ApiError { message } => write!(f, "RxNav reported an error: {}", message),
+            RxNormError::ResponseTooLarge { limit } => {
+                write!(f, "RxNav response exceeded the {} byte limit", limit)
+            }
+            RxNormError::InactiveConcepts(rxcuis) => {
+                let rxcuis: Vec<String> = rxcuis.iter().map(u32::to_string).collect();
+                write!(
+                    f,
+                    "RxCUIs not active in the current release: {}",
+                    rxcuis.join(", ")
+                )
+            }
+            RxNormError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl RxNormError {
+    /// A short, stable name for the kind of error, such as `timeout` or
+    /// `rate_limited` for a 429 or 503 status, for counting errors in logs
+    /// and metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RxNormError::Http(_) => "http",
+            RxNormError::Timeout => "timeout",
+            RxNormError::UnexpectedStatus(status) if crate::is_throttled(*status) => "rate_limited",
+            RxNormError::UnexpectedStatus(_) => "unexpected_status",
+            RxNormError::JsonParse(_) => "json_parse",
+            RxNormError::UnexpectedContentType { .. } => "unexpected_content_type",
+            RxNormError::IdParse(_) => "id_parse",
+            RxNormError::InvalidInput(_) => "invalid_input",
+            RxNormError::NoFixture(_) => "no_fixture",
+            RxNormError::VersionTooOld { .. } => "version_too_old",
+            RxNormError::Config(_) => "config",
+            RxNormError::ApiError { .. } => "api_error",
+            RxNormError::ResponseTooLarge { .. } => "response_too_large",
+            RxNormError::InactiveConcepts(_) => "inactive_concepts",
+            RxNormError::Io(_) => "io",
+        }
+    }
+}
+
+impl std::error::Error for RxNormError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RxNormError::Http(e) => Some(e),
+            RxNormError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for RxNormError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            RxNormError::Timeout
+        } else {
+            RxNormError::Http(e)
+        }
+    }
+}
+
+impl From<std::io::Error> for RxNormError {
+    fn from(e: std::io::Error) -> Self {
+        RxNormError::Io(e)
+    }
+}
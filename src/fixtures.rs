@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{normalize_term, RxNormClient, RxNormError};
+
+/// Canned `find_rxcui` results keyed by normalized drug name.
+pub(crate) type Fixtures = HashMap<String, Option<Vec<u32>>>;
+
+impl RxNormClient {
+    /// Creates an offline client whose [`find_rxcui`](Self::find_rxcui)
+    /// answers from `fixtures` instead of RxNav, for deterministic tests of
+    /// code built on this crate.
+    ///
+    /// Names are matched after the same trimming and whitespace collapsing
+    /// `find_rxcui` applies. A name without a fixture, and every other method
+    /// that would send a request, fails with [`RxNormError::NoFixture`]; an
+    /// offline client never touches the network.
+    pub fn with_fixtures(fixtures: HashMap<String, Option<Vec<u32>>>) -> Self {
+        let fixtures = fixtures
+            .into_iter()
+            .map(|(drug, rxcuis)| (normalize_term(&drug).unwrap_or(drug), rxcuis))
+            .collect();
+        let mut rx_client = Self::builder().build();
+        rx_client.fixtures = Some(Arc::new(fixtures));
+        rx_client
+    }
+}
+
+/// The fixture for an already normalized `drug`.
+pub(crate) fn fixture(fixtures: &Fixtures, drug: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+    fixtures
+        .get(drug)
+        .cloned()
+        .ok_or_else(|| RxNormError::NoFixture(drug.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixtures_answer_without_network() {
+        let mut fixtures = HashMap::new();
+        fixtures.insert(String::from("Vitamin  C"), Some(vec![1151]));
+        fixtures.insert(String::from("unobtainium"), None);
+        let rx_client = RxNormClient::with_fixtures(fixtures);
+        assert_eq!(
+            Some(vec![1151]),
+            rx_client.find_rxcui(" Vitamin C ").await.unwrap()
+        );
+        assert_eq!(None, rx_client.find_rxcui("unobtainium").await.unwrap());
+        assert!(matches!(
+            rx_client.find_rxcui("aspirin").await,
+            Err(RxNormError::NoFixture(drug)) if drug == "aspirin"
+        ));
+        assert!(matches!(
+            rx_client.get_version().await,
+            Err(RxNormError::NoFixture(_))
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{RxNormClient, RxNormError};
+
+/// The representation RxNav answers in, chosen by the endpoint's extension.
+/// The typed API always uses JSON; XML is for passing responses through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Xml,
+}
+
+impl ResponseFormat {
+    /// The extension RxNav endpoints take for this format, e.g. `"json"`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "json",
+            ResponseFormat::Xml => "xml",
+        }
+    }
+
+    /// `path` with this format's extension appended.
+    pub(crate) fn path(&self, path: &str) -> String {
+        format!("{}.{}", path, self.extension())
+    }
+}
+
+impl RxNormClient {
+    /// Sends a GET request to any RxNav endpoint in the given format and
+    /// returns the body untouched.
+    ///
+    /// `path` is the endpoint without its extension, e.g. `"/rxcui/161/related"`.
+    /// Like [`call_endpoint`](Self::call_endpoint), the request gets the usual
+    /// retries, timeout and rate limiting.
+    pub async fn call_endpoint_as(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+        format: ResponseFormat,
+    ) -> Result<String, RxNormError> {
+        let url = self.url(&format.path(path));
+        self.get(&url, params).await
+    }
+
+    /// Performs the same request as [`find_rxcui`](Self::find_rxcui) but
+    /// returns RxNav's XML response untouched.
+    pub async fn find_rxcui_raw_xml(&self, drug: &str) -> Result<String, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let url = self.url(&ResponseFormat::Xml.path("/rxcui"));
+        let query = self.scoped(&[("name", drug), ("search", self.search_mode.as_param())])?;
+        self.get(&url, &query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    #[tokio::test]
+    async fn test_xml_body_is_returned_untouched() {
+        let body = "<rxnormdata><idGroup><rxnormId>1151</rxnormId></idGroup></rxnormdata>";
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .build();
+        assert_eq!(
+            body,
+            rx_client.find_rxcui_raw_xml("vitamin c").await.unwrap()
+        );
+    }
+}
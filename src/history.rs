@@ -0,0 +1,490 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_json, parse_rxcui, rxcui_path, RelatedConcept, RxNormClient, RxNormError};
+
+/// How many remappings [`RxNormClient::is_remapped_to`] follows before
+/// giving up.
+const MAX_REMAP_DEPTH: usize = 5;
+
+/// The status of an RxCUI in the current RxNorm release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub enum ConceptStatus {
+    Active,
+    /// Retired without a successor.
+    Obsolete,
+    /// Retired and merged into one or more other concepts.
+    Remapped,
+    /// Retired and replaced by a quantified concept.
+    Quantified,
+    /// Not in the current release, but present in an earlier one.
+    NotCurrent,
+    /// Never known to RxNorm.
+    Unknown,
+    /// A status RxNav reports that this crate does not know yet.
+    Other(String),
+}
+
+impl ConceptStatus {
+    /// The status name as RxNav spells it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ConceptStatus::Active => "Active",
+            ConceptStatus::Obsolete => "Obsolete",
+            ConceptStatus::Remapped => "Remapped",
+            ConceptStatus::Quantified => "Quantified",
+            ConceptStatus::NotCurrent => "NotCurrent",
+            ConceptStatus::Unknown => "Unknown",
+            ConceptStatus::Other(status) => status,
+        }
+    }
+}
+
+impl From<&str> for ConceptStatus {
+    fn from(status: &str) -> Self {
+        match status {
+            "Active" => ConceptStatus::Active,
+            "Obsolete" => ConceptStatus::Obsolete,
+            "Remapped" => ConceptStatus::Remapped,
+            "Quantified" => ConceptStatus::Quantified,
+            "NotCurrent" => ConceptStatus::NotCurrent,
+            "" | "UNKNOWN" | "Unknown" => ConceptStatus::Unknown,
+            other => ConceptStatus::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ConceptStatus {
+    fn from(status: String) -> Self {
+        ConceptStatus::from(status.as_str())
+    }
+}
+
+impl From<ConceptStatus> for String {
+    fn from(status: ConceptStatus) -> Self {
+        match status {
+            ConceptStatus::Other(status) => status,
+            status => status.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConceptStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The history status of a concept, as returned by `getRxcuiHistoryStatus`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryStatus {
+    pub status: ConceptStatus,
+    /// The concepts a remapped RxCUI now points to. Empty for any other status.
+    pub remapped_to: Vec<RelatedConcept>,
+    /// The clinical drugs (SCDs) derived from the concept, each named by
+    /// ingredient, strength and dose form. Empty when RxNav lists none.
+    #[serde(default)]
+    pub scd_concepts: Vec<ScdConcept>,
+    /// The ingredient strengths of the concept, each with its base of
+    /// strength substance (BoSS). Empty when RxNav lists none.
+    #[serde(default)]
+    pub boss: Vec<BossStrength>,
+}
+
+/// A clinical drug derived from a concept, from
+/// [`HistoryStatus::scd_concepts`]. The name can be split with
+/// [`parse_drug_components`](crate::parse_drug_components).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScdConcept {
+    pub rxcui: u32,
+    /// The name, e.g. `acetaminophen 325 MG Oral Tablet`.
+    pub name: String,
+}
+
+/// One ingredient strength of a concept and the substance it is expressed
+/// in, from [`HistoryStatus::boss`]. Values are kept as RxNav writes them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BossStrength {
+    /// The ingredient, e.g. `metoprolol`.
+    pub base_rxcui: Option<u32>,
+    pub base_name: String,
+    /// The base of strength substance, e.g. `metoprolol tartrate`.
+    pub boss_rxcui: Option<u32>,
+    pub boss_name: String,
+    /// The numerator of the strength, e.g. `25` with unit `MG`.
+    pub numerator_value: String,
+    pub numerator_unit: String,
+    /// The denominator of the strength, e.g. `1` with unit `EACH`.
+    pub denominator_value: String,
+    pub denominator_unit: String,
+}
+
+/// Where an RxCUI should point in the current release, as reported by
+/// [`RxNormClient::migrate_rxcuis`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Migration {
+    pub status: ConceptStatus,
+    /// The RxCUI to use instead: the id itself when active, the first remap
+    /// target when remapped, and `None` when retired without a successor.
+    pub new_rxcui: Option<u32>,
+}
+
+impl HistoryStatus {
+    /// The migration for `rxcui` given this status.
+    pub fn migration(&self, rxcui: u32) -> Migration {
+        let new_rxcui = match self.remapped_to.first() {
+            Some(target) => Some(target.rxcui),
+            None if self.status == ConceptStatus::Active => Some(rxcui),
+            None => None,
+        };
+        Migration {
+            status: self.status.clone(),
+            new_rxcui,
+        }
+    }
+}
+
+impl RxNormClient {
+    /// Reports whether a concept is still active or has been retired or
+    /// remapped, along with any concepts it was remapped to and the
+    /// clinical drugs and ingredient strengths RxNav lists for it.
+    pub async fn history_status(&self, rxcui: u32) -> Result<HistoryStatus, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "historystatus.json")?);
+        let body = self.get(&url, &[]).await?;
+        parse_history_status(&body)
+    }
+
+    /// Looks up the history status of each RxCUI and maps it to the id it
+    /// should be replaced with.
+    ///
+    /// This sends one request per distinct RxCUI, one after another.
+    pub async fn migrate_rxcuis(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<HashMap<u32, Migration>, RxNormError> {
+        let mut migrations = HashMap::new();
+        for &rxcui in rxcuis {
+            if migrations.contains_key(&rxcui) {
+                continue;
+            }
+            let status = self.history_status(rxcui).await?;
+            migrations.insert(rxcui, status.migration(rxcui));
+        }
+        Ok(migrations)
+    }
+
+    /// Checks that every RxCUI is active in the current release, e.g. as a
+    /// data-quality gate, failing with [`RxNormError::InactiveConcepts`]
+    /// listing, in input order, each one that is retired, remapped or unknown.
+    ///
+    /// This sends one history request per distinct RxCUI, one after another.
+    pub async fn assert_all_active(&self, rxcuis: &[u32]) -> Result<(), RxNormError> {
+        let mut checked = HashSet::new();
+        let mut inactive = Vec::new();
+        for &rxcui in rxcuis {
+            if !checked.insert(rxcui) {
+                continue;
+            }
+            if self.history_status(rxcui).await?.status != ConceptStatus::Active {
+                inactive.push(rxcui);
+            }
+        }
+        if inactive.is_empty() {
+            Ok(())
+        } else {
+            Err(RxNormError::InactiveConcepts(inactive))
+        }
+    }
+
+    /// Whether the retired concept `old` was remapped into `new`, directly
+    /// or through a chain of later remappings.
+    ///
+    /// Chains are followed for up to five remappings, with one history
+    /// request per concept on the way. An active `old` gives `false`.
+    pub async fn is_remapped_to(&self, old: u32, new: u32) -> Result<bool, RxNormError> {
+        let mut seen = HashSet::new();
+        seen.insert(old);
+        let mut frontier = vec![old];
+        for _ in 0..MAX_REMAP_DEPTH {
+            let mut next = Vec::new();
+            for rxcui in frontier {
+                for target in self.history_status(rxcui).await?.remapped_to {
+                    if target.rxcui == new {
+                        return Ok(true);
+                    }
+                    if seen.insert(target.rxcui) {
+                        next.push(target.rxcui);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        Ok(false)
+    }
+}
+
+#[derive(Deserialize)]
+struct HistoryStatusResponse {
+    #[serde(rename = "rxcuiStatusHistory", default)]
+    rxcui_status_history: StatusHistory,
+}
+
+#[derive(Deserialize, Default)]
+struct StatusHistory {
+    #[serde(rename = "metaData")]
+    meta_data: Option<MetaData>,
+    #[serde(rename = "derivedConcepts")]
+    derived_concepts: Option<DerivedConcepts>,
+    #[serde(rename = "definitionalFeatures")]
+    definitional_features: Option<DefinitionalFeatures>,
+}
+
+#[derive(Deserialize, Default)]
+struct MetaData {
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Deserialize, Default)]
+struct DerivedConcepts {
+    #[serde(rename = "remappedConcept", default)]
+    remapped_concept: Vec<RemappedConcept>,
+    #[serde(rename = "scdConcept", default)]
+    scd_concept: Vec<RawScdConcept>,
+}
+
+#[derive(Deserialize)]
+struct RawScdConcept {
+    #[serde(rename = "scdConceptRxcui")]
+    rxcui: String,
+    #[serde(rename = "scdConceptName", default)]
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+struct DefinitionalFeatures {
+    #[serde(rename = "ingredientAndStrength", default)]
+    ingredient_and_strength: Vec<IngredientAndStrength>,
+}
+
+#[derive(Deserialize)]
+struct IngredientAndStrength {
+    #[serde(rename = "baseRxcui", default)]
+    base_rxcui: String,
+    #[serde(rename = "baseName", default)]
+    base_name: String,
+    #[serde(rename = "bossRxcui", default)]
+    boss_rxcui: String,
+    #[serde(rename = "bossName", default)]
+    boss_name: String,
+    #[serde(rename = "numeratorValue", default)]
+    numerator_value: String,
+    #[serde(rename = "numeratorUnit", default)]
+    numerator_unit: String,
+    #[serde(rename = "denominatorValue", default)]
+    denominator_value: String,
+    #[serde(rename = "denominatorUnit", default)]
+    denominator_unit: String,
+}
+
+/// An RxCUI that RxNav may leave blank.
+fn parse_optional_rxcui(id: &str) -> Result<Option<u32>, RxNormError> {
+    if id.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_rxcui(id).map(Some)
+    }
+}
+
+#[derive(Deserialize)]
+struct RemappedConcept {
+    #[serde(rename = "remappedRxCui")]
+    remapped_rxcui: String,
+    #[serde(rename = "remappedName", default)]
+    remapped_name: String,
+    #[serde(rename = "remappedTTY", default)]
+    remapped_tty: String,
+}
+
+pub(crate) fn parse_history_status(body: &str) -> Result<HistoryStatus, RxNormError> {
+    let response: HistoryStatusResponse = parse_json(body)?;
+    let history = response.rxcui_status_history;
+    let status = history.meta_data.unwrap_or_default().status;
+    let derived = history.derived_concepts.unwrap_or_default();
+    Ok(HistoryStatus {
+        status: ConceptStatus::from(status),
+        remapped_to: derived
+            .remapped_concept
+            .into_iter()
+            .map(|c| {
+                Ok(RelatedConcept {
+                    rxcui: parse_rxcui(&c.remapped_rxcui)?,
+                    name: c.remapped_name,
+                    tty: c.remapped_tty,
+                    suppress: String::new(),
+                    language: String::new(),
+                })
+            })
+            .collect::<Result<_, RxNormError>>()?,
+        scd_concepts: derived
+            .scd_concept
+            .into_iter()
+            .map(|c| {
+                Ok(ScdConcept {
+                    rxcui: parse_rxcui(&c.rxcui)?,
+                    name: c.name,
+                })
+            })
+            .collect::<Result<_, RxNormError>>()?,
+        boss: history
+            .definitional_features
+            .unwrap_or_default()
+            .ingredient_and_strength
+            .into_iter()
+            .map(|s| {
+                Ok(BossStrength {
+                    base_rxcui: parse_optional_rxcui(&s.base_rxcui)?,
+                    base_name: s.base_name,
+                    boss_rxcui: parse_optional_rxcui(&s.boss_rxcui)?,
+                    boss_name: s.boss_name,
+                    numerator_value: s.numerator_value,
+                    numerator_unit: s.numerator_unit,
+                    denominator_value: s.denominator_value,
+                    denominator_unit: s.denominator_unit,
+                })
+            })
+            .collect::<Result<_, RxNormError>>()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    #[test]
+    fn test_parse_remapped_status() {
+        let body = r#"{"rxcuiStatusHistory":{"metaData":{"status":"Remapped","source":"RXNORM"},
+            "derivedConcepts":{"remappedConcept":[{"remappedRxCui":"1367410","remappedName":"atorvastatin calcium trihydrate","remappedTTY":"PIN"}]}}}"#;
+        let status = parse_history_status(body).unwrap();
+        assert_eq!(ConceptStatus::Remapped, status.status);
+        assert_eq!(1367410, status.remapped_to[0].rxcui);
+    }
+
+    #[test]
+    fn test_parse_active_status() {
+        let body =
+            r#"{"rxcuiStatusHistory":{"metaData":{"status":"Active"},"derivedConcepts":null}}"#;
+        let status = parse_history_status(body).unwrap();
+        assert_eq!(ConceptStatus::Active, status.status);
+        assert!(status.remapped_to.is_empty());
+        assert!(status.scd_concepts.is_empty());
+        assert!(status.boss.is_empty());
+    }
+
+    #[test]
+    fn test_parse_scd_concepts_and_boss() {
+        let body = r#"{"rxcuiStatusHistory":{"metaData":{"status":"Active"},
+            "definitionalFeatures":{"ingredientAndStrength":[{"baseRxcui":"6918","baseName":"metoprolol",
+                "bossRxcui":"221124","bossName":"metoprolol tartrate","activeIngredientRxcui":"221124",
+                "numeratorValue":"25","numeratorUnit":"MG","denominatorValue":"1","denominatorUnit":"EACH"}]},
+            "derivedConcepts":{"scdConcept":[{"scdConceptRxcui":"866924","scdConceptName":"metoprolol tartrate 25 MG Oral Tablet"}]}}}"#;
+        let status = parse_history_status(body).unwrap();
+        assert_eq!(
+            vec![ScdConcept {
+                rxcui: 866924,
+                name: String::from("metoprolol tartrate 25 MG Oral Tablet"),
+            }],
+            status.scd_concepts
+        );
+        assert_eq!(
+            vec![BossStrength {
+                base_rxcui: Some(6918),
+                base_name: String::from("metoprolol"),
+                boss_rxcui: Some(221124),
+                boss_name: String::from("metoprolol tartrate"),
+                numerator_value: String::from("25"),
+                numerator_unit: String::from("MG"),
+                denominator_value: String::from("1"),
+                denominator_unit: String::from("EACH"),
+            }],
+            status.boss
+        );
+        let blank = r#"{"rxcuiStatusHistory":{"definitionalFeatures":{"ingredientAndStrength":[{"bossRxcui":""}]}}}"#;
+        assert_eq!(
+            None,
+            parse_history_status(blank).unwrap().boss[0].boss_rxcui
+        );
+    }
+
+    #[test]
+    fn test_migration_targets() {
+        let remapped = r#"{"rxcuiStatusHistory":{"metaData":{"status":"Remapped"},
+            "derivedConcepts":{"remappedConcept":[{"remappedRxCui":"1367410"}]}}}"#;
+        let active = r#"{"rxcuiStatusHistory":{"metaData":{"status":"Active"}}}"#;
+        let obsolete = r#"{"rxcuiStatusHistory":{"metaData":{"status":"Obsolete"}}}"#;
+        let migration = |body, rxcui| parse_history_status(body).unwrap().migration(rxcui);
+        assert_eq!(Some(1367410), migration(remapped, 83367).new_rxcui);
+        assert_eq!(Some(1151), migration(active, 1151).new_rxcui);
+        assert_eq!(None, migration(obsolete, 1234).new_rxcui);
+    }
+
+    #[tokio::test]
+    async fn test_remap_chain_is_followed() {
+        let addr = serve(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"{"rxcuiStatusHistory":{"metaData":{"status":"Remapped"},
+                    "derivedConcepts":{"remappedConcept":[{"remappedRxCui":"200"}]}}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"rxcuiStatusHistory":{"metaData":{"status":"Remapped"},
+                    "derivedConcepts":{"remappedConcept":[{"remappedRxCui":"300"}]}}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        assert!(rx_client.is_remapped_to(100, 300).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_assert_all_active_lists_stale_ids() {
+        let status = |status: &str| {
+            response(
+                "200 OK",
+                &[],
+                &format!(
+                    r#"{{"rxcuiStatusHistory":{{"metaData":{{"status":"{}"}}}}}}"#,
+                    status
+                ),
+            )
+        };
+        let addr = serve(vec![
+            status("Active"),
+            status("Remapped"),
+            status("Obsolete"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .build();
+        let actual = rx_client.assert_all_active(&[161, 105, 161, 7]).await;
+        assert!(matches!(
+            actual,
+            Err(RxNormError::InactiveConcepts(ids)) if ids == vec![105, 7]
+        ));
+        assert!(rx_client.assert_all_active(&[]).await.is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{RxNormClient, RxNormError};
+
+/// Identifier systems that `findRxcuiById` can map to RxCUIs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub enum IdType {
+    Atc,
+    Cvx,
+    Drugbank,
+    GcnSeqno,
+    Gfc,
+    Gpi,
+    Hcpcs,
+    HicSeqn,
+    Mesh,
+    MmslCode,
+    Ndc,
+    Nui,
+    Snomedct,
+    SplSetId,
+    Umlscui,
+    UniiCode,
+    Usp,
+    Vuid,
+    /// Any other idtype listed by RxNav's `/idtypes.json`.
+    Other(String),
+}
+
+impl IdType {
+    /// The value RxNav expects in the `idtype` query parameter.
+    pub fn as_str(&self) -> &str {
+        match self {
+            IdType::Atc => "ATC",
+            IdType::Cvx => "CVX",
+            IdType::Drugbank => "DRUGBANK",
+            IdType::GcnSeqno => "GCN_SEQNO",
+            IdType::Gfc => "GFC",
+            IdType::Gpi => "GPI",
+            IdType::Hcpcs => "HCPCS",
+            IdType::HicSeqn => "HIC_SEQN",
+            IdType::Mesh => "MESH",
+            IdType::MmslCode => "MMSL_CODE",
+            IdType::Ndc => "NDC",
+            IdType::Nui => "NUI",
+            IdType::Snomedct => "SNOMEDCT",
+            IdType::SplSetId => "SPL_SET_ID",
+            IdType::Umlscui => "UMLSCUI",
+            IdType::UniiCode => "UNII_CODE",
+            IdType::Usp => "USP",
+            IdType::Vuid => "VUID",
+            IdType::Other(idtype) => idtype,
+        }
+    }
+}
+
+impl From<&str> for IdType {
+    fn from(idtype: &str) -> Self {
+        match idtype {
+            "ATC" => IdType::Atc,
+            "CVX" => IdType::Cvx,
+            "DRUGBANK" => IdType::Drugbank,
+            "GCN_SEQNO" => IdType::GcnSeqno,
+            "GFC" => IdType::Gfc,
+            "GPI" => IdType::Gpi,
+            "HCPCS" => IdType::Hcpcs,
+            "HIC_SEQN" => IdType::HicSeqn,
+            "MESH" => IdType::Mesh,
+            "MMSL_CODE" => IdType::MmslCode,
+            "NDC" => IdType::Ndc,
+            "NUI" => IdType::Nui,
+            "SNOMEDCT" => IdType::Snomedct,
+            "SPL_SET_ID" => IdType::SplSetId,
+            "UMLSCUI" => IdType::Umlscui,
+            "UNII_CODE" => IdType::UniiCode,
+            "USP" => IdType::Usp,
+            "VUID" => IdType::Vuid,
+            other => IdType::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for IdType {
+    fn from(idtype: String) -> Self {
+        IdType::from(idtype.as_str())
+    }
+}
+
+impl From<IdType> for String {
+    fn from(idtype: IdType) -> Self {
+        match idtype {
+            IdType::Other(idtype) => idtype,
+            idtype => idtype.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for IdType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl RxNormClient {
+    /// Finds the RxCUIs that an identifier from another terminology maps to.
+    ///
+    /// A blank `id`, or a `search` or `name` default query parameter that
+    /// RxNav would quietly misread alongside `idtype`, gives
+    /// [`RxNormError::InvalidInput`] without sending a request.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rxnormalizer::{IdType, RxNormClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let rx_client = RxNormClient::builder().build();
+    ///     let ids = rx_client.find_rxcui_by_id("N02BE01", IdType::Atc).await.unwrap();
+    ///     assert_eq!(Some(vec![161]), ids);
+    /// }
+    /// ```
+    pub async fn find_rxcui_by_id(
+        &self,
+        id: &str,
+        idtype: IdType,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.search_ids(&[("idtype", idtype.as_str()), ("id", id.trim())])
+            .await
+    }
+}
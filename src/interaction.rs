@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use crate::related::RawConcept;
+use crate::{check_rxcui, join_list, parse_json, RelatedConcept, RxNormClient, RxNormError};
+
+/// A drug-drug interaction reported by the RxNav interaction API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub description: String,
+    pub severity: String,
+    /// The interaction source, e.g. `DrugBank` or `ONCHigh`.
+    pub source: String,
+    pub pair: (RelatedConcept, RelatedConcept),
+}
+
+impl RxNormClient {
+    /// Finds the known interactions of a single concept.
+    pub async fn find_interactions(&self, rxcui: u32) -> Result<Vec<Interaction>, RxNormError> {
+        check_rxcui(rxcui)?;
+        let url = self.url("/interaction/interaction.json");
+        let body = self.get(&url, &[("rxcui", &rxcui.to_string())]).await?;
+        parse_interactions(&body)
+    }
+
+    /// Finds the interactions between the members of a list of concepts. An
+    /// empty list gives an empty vec without sending a request.
+    pub async fn find_interactions_between(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<Vec<Interaction>, RxNormError> {
+        if rxcuis.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = self.url("/interaction/list.json");
+        let ids = join_list(rxcuis);
+        let body = self.get(&url, &[("rxcuis", &ids)]).await?;
+        parse_interaction_list(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct InteractionResponse {
+    #[serde(rename = "interactionTypeGroup", default)]
+    interaction_type_group: Vec<InteractionTypeGroup>,
+}
+
+#[derive(Deserialize)]
+struct InteractionTypeGroup {
+    #[serde(rename = "sourceName", default)]
+    source_name: String,
+    #[serde(rename = "interactionType", default)]
+    interaction_type: Vec<InteractionType>,
+}
+
+#[derive(Deserialize)]
+struct InteractionListResponse {
+    #[serde(rename = "fullInteractionTypeGroup", default)]
+    full_interaction_type_group: Vec<FullInteractionTypeGroup>,
+}
+
+#[derive(Deserialize)]
+struct FullInteractionTypeGroup {
+    #[serde(rename = "sourceName", default)]
+    source_name: String,
+    #[serde(rename = "fullInteractionType", default)]
+    full_interaction_type: Vec<InteractionType>,
+}
+
+#[derive(Deserialize)]
+struct InteractionType {
+    #[serde(rename = "interactionPair", default)]
+    interaction_pair: Vec<InteractionPair>,
+}
+
+#[derive(Deserialize)]
+struct InteractionPair {
+    #[serde(rename = "interactionConcept", default)]
+    interaction_concept: Vec<InteractionConcept>,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct InteractionConcept {
+    #[serde(rename = "minConceptItem")]
+    min_concept_item: RawConcept,
+}
+
+pub(crate) fn parse_interactions(body: &str) -> Result<Vec<Interaction>, RxNormError> {
+    let response: InteractionResponse = parse_json(body)?;
+    let mut interactions = Vec::new();
+    for group in response.interaction_type_group {
+        for interaction_type in group.interaction_type {
+            collect_pairs(
+                &group.source_name,
+                interaction_type.interaction_pair,
+                &mut interactions,
+            )?;
+        }
+    }
+    Ok(interactions)
+}
+
+pub(crate) fn parse_interaction_list(body: &str) -> Result<Vec<Interaction>, RxNormError> {
+    let response: InteractionListResponse = parse_json(body)?;
+    let mut interactions = Vec::new();
+    for group in response.full_interaction_type_group {
+        for interaction_type in group.full_interaction_type {
+            collect_pairs(
+                &group.source_name,
+                interaction_type.interaction_pair,
+                &mut interactions,
+            )?;
+        }
+    }
+    Ok(interactions)
+}
+
+fn collect_pairs(
+    source: &str,
+    pairs: Vec<InteractionPair>,
+    interactions: &mut Vec<Interaction>,
+) -> Result<(), RxNormError> {
+    for pair in pairs {
+        let mut concepts = pair.interaction_concept.into_iter();
+        let (first, second) = match (concepts.next(), concepts.next()) {
+            (Some(first), Some(second)) => (first, second),
+            _ => {
+                return Err(RxNormError::JsonParse(String::from(
+                    "interaction pair without two concepts",
+                )))
+            }
+        };
+        interactions.push(Interaction {
+            description: pair.description,
+            severity: pair.severity,
+            source: source.to_string(),
+            pair: (
+                first.min_concept_item.into_concept()?,
+                second.min_concept_item.into_concept()?,
+            ),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interaction_pairs() {
+        let body = r#"{"interactionTypeGroup":[{"sourceName":"DrugBank","interactionType":[{
+            "minConceptItem":{"rxcui":"88014","name":"rizatriptan","tty":"IN"},
+            "interactionPair":[{"interactionConcept":[
+                {"minConceptItem":{"rxcui":"88014","name":"rizatriptan","tty":"IN"}},
+                {"minConceptItem":{"rxcui":"41493","name":"meperidine","tty":"IN"}}],
+            "severity":"N/A","description":"Serotonin syndrome risk."}]}]}]}"#;
+        let interactions = parse_interactions(body).unwrap();
+        assert_eq!(1, interactions.len());
+        assert_eq!("DrugBank", interactions[0].source);
+        assert_eq!(41493, interactions[0].pair.1.rxcui);
+    }
+
+    #[test]
+    fn test_parse_interaction_list() {
+        let body = r#"{"fullInteractionTypeGroup":[{"sourceName":"ONCHigh","fullInteractionType":[{
+            "interactionPair":[{"interactionConcept":[
+                {"minConceptItem":{"rxcui":"207106","name":"fluconazole 50 MG","tty":"SCD"}},
+                {"minConceptItem":{"rxcui":"656659","name":"simvastatin 40 MG","tty":"SCD"}}],
+            "severity":"high","description":"Increased risk of myopathy."}]}]}]}"#;
+        let interactions = parse_interaction_list(body).unwrap();
+        assert_eq!("high", interactions[0].severity);
+        assert_eq!(207106, interactions[0].pair.0.rxcui);
+    }
+
+    #[test]
+    fn test_parse_incomplete_pair() {
+        let body = r#"{"interactionTypeGroup":[{"sourceName":"DrugBank","interactionType":[{
+            "interactionPair":[{"interactionConcept":[
+                {"minConceptItem":{"rxcui":"88014","name":"rizatriptan","tty":"IN"}}]}]}]}]}"#;
+        assert!(matches!(
+            parse_interactions(body),
+            Err(RxNormError::JsonParse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_empty_list_sends_no_request() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        assert!(rx_client
+            .find_interactions_between(&[])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,23 +1,267 @@
 //! # RxNorm
 //! Wrapper for the RxNav [RxNorm API](https://rxnav.nlm.nih.gov/RxNormAPIs.html)
+//!
+//! No public method panics on anything RxNav sends back: malformed or
+//! unexpected responses come back as [`RxNormError`]s.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
-use reqwest::{Client, Error, Response};
-use tokio::time::{sleep, Duration};
+use log::{debug, warn};
+use reqwest::header::{
+    HeaderMap, ACCEPT_LANGUAGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER, USER_AGENT,
+};
+use reqwest::{Error, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fmt;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
 
-const RXNAV_URL: &str = "https://rxnav.nlm.nih.gov/REST/rxcui.json";
+use approximate::{best_rxcui, parse_candidates};
+use cache::{name_key, Cache};
+use fixtures::{fixture, Fixtures};
+use meta::header_map;
+use rate_limit::{InFlightLimit, RateLimiter, ThrottleCounters};
 
+mod approximate;
+mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
+mod cache;
+mod components;
+mod concepts;
+mod display;
+mod drug_name;
+mod drugs;
+mod env;
+mod error;
+mod fixtures;
+mod format;
+mod history;
+mod idtype;
+mod interaction;
+mod matcher;
+mod meta;
+mod ndc;
+mod observer;
+mod properties;
+mod rate_limit;
+mod related;
+mod relationship;
+mod resolve;
+mod retry;
+mod rxclass;
+mod rxnorm;
+mod rxterms;
+mod search;
+mod spelling;
+mod term_type;
+mod version;
+
+pub use approximate::ApproximateCandidate;
+pub use batch::BatchSummary;
+#[cfg(feature = "blocking")]
+pub use blocking::RxNormBlockingClient;
+pub use builder::RxNormClientBuilder;
+pub use cache::CacheStats;
+pub use drug_name::{parse_drug_components, DrugComponents, Strength};
+pub use error::RxNormError;
+pub use format::ResponseFormat;
+pub use history::{BossStrength, ConceptStatus, HistoryStatus, Migration, ScdConcept};
+pub use idtype::IdType;
+pub use interaction::Interaction;
+pub use matcher::LocalMatcher;
+pub use meta::ResponseMeta;
+pub use ndc::{HistoricalNdc, NdcProperties};
+pub use observer::RequestObserver;
+pub use properties::{PropCategory, PropConcept, RxConceptProperties};
+pub use rate_limit::ThrottleStats;
+pub use related::{ConceptGroup, MergedConcept, RelatedConcept};
+pub use resolve::{Diagnosis, MatchGroups, Resolution, ResolveOutcome, ResolvedDrug};
+pub use retry::{should_retry, Backoff, RetryPolicy};
+pub use rxclass::AtcClass;
+pub use rxnorm::RxNorm;
+pub use rxterms::RxTermsInfo;
+use search::check_search_params;
+pub use search::{SearchMode, SourceScope};
+pub use term_type::{most_specific, TermType};
+pub use version::RxNormVersion;
+
+/// Base URL of the public RxNav REST API.
+pub const DEFAULT_BASE_URL: &str = "https://rxnav.nlm.nih.gov/REST";
+
+/// Number of times a failed request is retried unless configured otherwise.
+pub const DEFAULT_MAX_RETRIES: u32 = 1;
+
+/// Time to wait between retries unless configured otherwise.
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Largest response body read unless configured otherwise, 16 MiB.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Longest drug name, in characters, searched for unless configured
+/// otherwise. Real names are far shorter; longer input is usually a whole
+/// free-text line in the wrong field.
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 300;
+
+/// How long a cached lookup is used before it is fetched again, unless
+/// configured otherwise. RxNorm publishes a new release monthly, so a day
+/// keeps stale mappings short-lived.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `User-Agent` sent with requests unless configured otherwise.
+pub const DEFAULT_USER_AGENT: &str = concat!("rxnormalizer/", env!("CARGO_PKG_VERSION"));
+
+/// Number of approximate candidates fetched when falling back to an
+/// approximate match.
+pub(crate) const FALLBACK_CANDIDATES: u32 = 5;
+
+/// Client for the RxNav REST API.
+///
+/// Cloning is cheap: clones share the HTTP connection pool, the cache, the
+/// rate limiter, the in-flight limit and the throttle statistics.
+#[derive(Clone)]
 pub struct RxNormClient {
     client: reqwest::Client,
-    normalize: bool,
+    search_mode: SearchMode,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    timeout: Option<Duration>,
+    cache: Option<Arc<Cache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    in_flight: Option<Arc<InFlightLimit>>,
+    throttle: Arc<ThrottleCounters>,
+    approximate_fallback: bool,
+    exact_fallback: bool,
+    min_approximate_score: i32,
+    sort_results: bool,
+    max_matches: Option<usize>,
+    exclude_suppressed: bool,
+    language: Option<String>,
+    user_agent: Option<String>,
+    observer: Option<Arc<dyn RequestObserver>>,
+    source_params: Vec<(&'static str, String)>,
+    max_response_bytes: Option<usize>,
+    max_name_length: Option<usize>,
+    truncate_long_names: bool,
+    default_params: Vec<(String, String)>,
+    fixtures: Option<Arc<Fixtures>>,
+    min_version: Option<String>,
+    version_checked: Arc<AtomicBool>,
 }
 
 impl RxNormClient {
-    pub fn new(client: reqwest::Client, normalize: bool) -> Self {
-        Self { client, normalize }
+    /// Creates a client using the given search mode. Passing a `bool` keeps
+    /// the historical behaviour: `true` searches exact then normalized.
+    pub fn new(client: reqwest::Client, search_mode: impl Into<SearchMode>) -> Self {
+        Self::builder()
+            .http_client(client)
+            .search_mode(search_mode.into())
+            .build()
+    }
+
+    /// Caches up to `capacity` `find_rxcui` results in memory, evicting the
+    /// least recently used entry when full, along with the display term list.
+    /// Names differing only in case share an entry, as RxNav matches them
+    /// alike; the name is still sent as given.
+    /// As many [`get_related_by_type`](Self::get_related_by_type) and
+    /// [`get_all_related`](Self::get_all_related) results are cached too.
+    /// Up to `capacity` responses that carried an `ETag` are kept as well
+    /// and revalidated with `If-None-Match`, so a `304 Not Modified` reuses
+    /// them. A capacity of zero disables caching.
+    ///
+    /// Entries expire after [`DEFAULT_CACHE_TTL`]; see
+    /// [`with_cache_ttl`](Self::with_cache_ttl). The whole cache is also
+    /// emptied when [`get_version`](Self::get_version) reports a different
+    /// RxNorm release than it last did, so calling it now and then drops
+    /// mappings from a superseded release.
+    pub fn with_cache(self, capacity: usize) -> Self {
+        self.with_cache_ttl(capacity, DEFAULT_CACHE_TTL)
+    }
+
+    /// Like [`with_cache`](Self::with_cache), but entries are fetched again
+    /// once they are older than `ttl`.
+    pub fn with_cache_ttl(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.cache =
+            NonZeroUsize::new(capacity).map(|capacity| Arc::new(Cache::new(capacity, ttl)));
+        self
+    }
+
+    /// Empties the cache, keeping its hit and miss counts. Does nothing when
+    /// caching is disabled.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Returns the cache's hit and miss counts, which are zero when caching is
+    /// disabled.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.as_deref().map(Cache::stats).unwrap_or_default()
+    }
+
+    /// How many requests currently hold one of the
+    /// [`max_in_flight`](RxNormClientBuilder::max_in_flight) permits, or
+    /// `None` when in-flight requests are not limited.
+    pub fn inflight_permits(&self) -> Option<usize> {
+        self.in_flight.as_deref().map(InFlightLimit::in_flight)
+    }
+
+    /// How long a request started now would wait for its
+    /// [`max_requests_per_second`](RxNormClientBuilder::max_requests_per_second)
+    /// slot, or `None` when requests are not rate limited.
+    pub fn rate_limit_delay(&self) -> Option<Duration> {
+        self.rate_limiter.as_deref().map(RateLimiter::backlog)
+    }
+
+    /// How often requests were throttled and retried since the client was
+    /// built, counted across its clones.
+    pub fn throttle_stats(&self) -> ThrottleStats {
+        self.throttle.stats()
+    }
+
+    /// The HTTP client every request is sent with, so it can be shared with
+    /// other code that wants the same connection pool and configuration.
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Returns a builder for configuring a client.
+    pub fn builder() -> RxNormClientBuilder {
+        RxNormClientBuilder::default()
+    }
+
+    /// Creates a client that talks to a different RxNav host, such as a
+    /// self-hosted RxNav-in-a-Box at `http://localhost:4000/REST`.
+    pub fn with_base_url(
+        client: reqwest::Client,
+        search_mode: impl Into<SearchMode>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self::builder()
+            .http_client(client)
+            .search_mode(search_mode.into())
+            .base_url(base_url)
+            .build()
     }
 
     /// Finds the RxCUI for a givin string.
     ///
+    /// The string is trimmed and runs of whitespace are collapsed to a single
+    /// space. An empty or whitespace-only string fails with
+    /// [`RxNormError::InvalidInput`] without sending a request.
+    ///
+    /// `Ok(Some(ids))` always holds at least one RxCUI. `Ok(None)` means RxNav
+    /// answered successfully but matched nothing, i.e. an empty `idGroup`.
+    /// Anything else is an error: a status outside 2xx, `404 Not Found`
+    /// included, gives [`RxNormError::UnexpectedStatus`], and a request that
+    /// still cannot be sent after the configured retries gives
+    /// [`RxNormError::Http`] or [`RxNormError::Timeout`].
+    ///
     /// # Examples
     /// ```rust
     ///use reqwest::Client;
@@ -28,77 +272,759 @@ impl RxNormClient {
     ///
     /// let http_client = Client::new();
     /// let rx_client = RxNormClient::new(http_client, true);
-    ///let vit_c: &String = &String::from("vit-c");
     ///
     /// // Calling RxNav
-    ///let actual: Vec<i32> = rx_client.find_rxcui(&vit_c).await.unwrap().expect("Could not find vit-c");
-    ///let expected: Vec<i32> = vec![1088438, 1151];
+    ///let actual: Vec<u32> = rx_client.find_rxcui("vit-c").await.unwrap().expect("Could not find vit-c");
+    ///let expected: Vec<u32> = vec![1088438, 1151];
     ///assert_eq!(expected, actual)
     /// }
     /// ```
     ///
+    /// With [`approximate_fallback`](RxNormClientBuilder::approximate_fallback)
+    /// enabled, a name with no match falls back to the top approximate
+    /// candidate's RxCUI. With
+    /// [`exclude_suppressed`](RxNormClientBuilder::exclude_suppressed) on,
+    /// every match costs an extra properties request to read its suppress
+    /// flag, and `None` is returned if all of them are suppressed.
+    pub async fn find_rxcui(&self, drug: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.find_name(drug, None).await
+    }
+
+    /// Like [`find_rxcui`](Self::find_rxcui), but gives up with
+    /// [`RxNormError::Timeout`] once `deadline` passes, including while waiting
+    /// to retry. Backoff sleeps are shortened so they never outlast the deadline.
+    pub async fn find_rxcui_with_deadline(
+        &self,
+        drug: &str,
+        deadline: impl Into<Instant>,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.find_name(drug, Some(deadline.into())).await
+    }
+
+    async fn find_name(
+        &self,
+        drug: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        if let Some(fixtures) = &self.fixtures {
+            return fixture(fixtures, drug);
+        }
+        let ids = self.find_name_with_suppressed(drug, deadline).await?;
+        self.without_suppressed(ids).await
+    }
+
+    /// Like `find_name` for an already normalized `drug`, keeping suppressed
+    /// concepts, for callers that look up properties themselves.
+    pub(crate) async fn find_name_with_suppressed(
+        &self,
+        drug: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let ids = match self.lookup_name(drug, self.search_mode, deadline).await {
+            result
+                if self.search_mode != SearchMode::Exact && self.falls_back_to_exact(&result) =>
+            {
+                self.lookup_name(drug, SearchMode::Exact, deadline).await?
+            }
+            result => result?,
+        };
+        if ids.is_some() || !self.approximate_fallback {
+            return Ok(ids);
+        }
+        let approximate = self.approximate_rxcui(drug, deadline).await?;
+        Ok(approximate.map(|(rxcui, _)| vec![rxcui]))
+    }
+
+    /// Whether a normalized search that gave `result` is tried again as an
+    /// exact one, under
+    /// [`fallback_to_exact_on_normalized_failure`](RxNormClientBuilder::fallback_to_exact_on_normalized_failure).
+    pub(crate) fn falls_back_to_exact(
+        &self,
+        result: &Result<Option<Vec<u32>>, RxNormError>,
+    ) -> bool {
+        self.exact_fallback && worth_exact_search(result)
+    }
+
+    pub(crate) async fn lookup_name(
+        &self,
+        drug: &str,
+        search_mode: SearchMode,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let key = name_key(drug, search_mode);
+        if let Some(cached) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            return Ok(cached);
+        }
+        let url = self.url("/rxcui.json");
+        let query = self.scoped(&[("name", drug), ("search", search_mode.as_param())])?;
+        let body = self.get_before(&url, &query, deadline).await?;
+        let ids = self.tidy_ids(parse_rxnorm_ids(&body)?);
+        if let Some(cache) = &self.cache {
+            cache.insert(key, ids.clone());
+        }
+        Ok(ids)
+    }
+
+    /// The best approximate match for `drug` and its score, if it reaches the
+    /// configured minimum score.
+    pub(crate) async fn approximate_rxcui(
+        &self,
+        drug: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Option<(u32, i32)>, RxNormError> {
+        let url = self.url("/approximateTerm.json");
+        let max_entries = FALLBACK_CANDIDATES.to_string();
+        let query = [("term", drug), ("maxEntries", max_entries.as_str())];
+        let body = self.get_before(&url, &query, deadline).await?;
+        let candidates = parse_candidates(&body)?;
+        Ok(best_rxcui(&candidates, self.min_approximate_score))
+    }
+
+    /// Performs the same request as [`find_rxcui`](Self::find_rxcui) but
+    /// returns RxNav's JSON response untouched, for fields the typed API does
+    /// not expose.
+    pub async fn find_rxcui_raw(&self, drug: &str) -> Result<serde_json::Value, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let url = self.url("/rxcui.json");
+        let query = self.scoped(&[("name", drug), ("search", self.search_mode.as_param())])?;
+        let body = self.get(&url, &query).await?;
+        parse_json(&body)
+    }
+
+    /// Sends a GET request to any RxNav endpoint and returns the JSON response
+    /// untouched, for endpoints and parameters the typed API does not cover.
     ///
+    /// `path` is joined onto the configured base URL, e.g.
+    /// `"/rxcui/161/related.json"`, and the request gets the same retries,
+    /// timeout and rate limiting as every other call. `params` are encoded
+    /// for you, so pass values such as `"a & b"` as they are; only `path`
+    /// must already be URL-safe.
+    pub async fn call_endpoint(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<serde_json::Value, RxNormError> {
+        let url = self.url(path);
+        let body = self.get(&url, params).await?;
+        parse_json(&body)
+    }
 
-    pub async fn find_rxcui(&self, drug: &String) -> Result<Option<Vec<i32>>, &'static str> {
-        let mode = if self.normalize { "2" } else { "0" };
-        let result = make_call(&drug, &self.client, &String::from(mode)).await;
-        let res = match result {
-            Ok(res) => res,
-            Err(e) => {
-                println!(
-                    "Caught an error of kind {}, going to wait 2 seconds and try again",
-                    e.to_string()
-                );
-                sleep(Duration::from_secs(2)).await;
-                make_call(&drug, &self.client, &String::from(mode))
-                    .await
-                    .unwrap()
+    /// Queries the `rxcui.json` id search with the given parameters.
+    pub(crate) async fn search_ids(
+        &self,
+        query: &[(&str, &str)],
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let url = self.url("/rxcui.json");
+        let body = self.get(&url, &self.scoped(query)?).await?;
+        Ok(self.tidy_ids(parse_rxnorm_ids(&body)?))
+    }
+
+    /// Adds the configured source scope's parameters to a search query,
+    /// rejecting combinations RxNav would silently misread.
+    fn scoped<'a>(
+        &'a self,
+        query: &[(&'a str, &'a str)],
+    ) -> Result<Vec<(&'a str, &'a str)>, RxNormError> {
+        let scope = self.source_params.iter().map(|(k, v)| (*k, v.as_str()));
+        let query: Vec<_> = query.iter().copied().chain(scope).collect();
+        let defaults = without_overridden(&self.default_params, &query);
+        check_search_params(query.iter().chain(&defaults))?;
+        Ok(query)
+    }
+
+    /// Sorts, deduplicates and truncates search results when configured to.
+    fn tidy_ids(&self, ids: Option<Vec<u32>>) -> Option<Vec<u32>> {
+        tidy_ids(ids, self.sort_results, self.max_matches)
+    }
+
+    /// Joins an endpoint path onto the configured base URL.
+    pub(crate) fn url(&self, path: &str) -> String {
+        join_url(&self.base_url, path)
+    }
+
+    /// The URL a GET request for `path` with `params` is sent to: the path
+    /// joined onto the base URL, then `params` and the
+    /// [default query parameters](RxNormClientBuilder::default_query_param)
+    /// they do not override, encoded as every request encodes them. Nothing
+    /// is sent, so this can check a configuration offline.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rxnormalizer::RxNormClient;
+    ///
+    /// let rx_client = RxNormClient::builder()
+    ///     .base_url("http://localhost:4000/REST/")
+    ///     .default_query_param("allsrc", "1")
+    ///     .build();
+    /// let url = rx_client.build_url("/rxcui.json", &[("name", "vit c")]).unwrap();
+    /// assert_eq!(
+    ///     "http://localhost:4000/REST/rxcui.json?name=vit+c&allsrc=1",
+    ///     url.as_str()
+    /// );
+    /// ```
+    pub fn build_url(&self, path: &str, params: &[(&str, &str)]) -> Result<Url, RxNormError> {
+        let request = self.get_request(&self.url(path), params).build()?;
+        Ok(request.url().clone())
+    }
+
+    /// A GET request for the full `url` with `query` and the default query
+    /// parameters it does not override.
+    fn get_request(&self, url: &str, query: &[(&str, &str)]) -> RequestBuilder {
+        let defaults = without_overridden(&self.default_params, query);
+        self.client.get(url).query(query).query(&defaults)
+    }
+
+    /// `drug` normalized and held to the configured maximum name length.
+    pub(crate) fn prepare_term(&self, drug: &str) -> Result<String, RxNormError> {
+        limit_term(
+            normalize_term(drug)?,
+            self.max_name_length,
+            self.truncate_long_names,
+        )
+    }
+
+    /// Sends a GET request to RxNav, retrying transport errors and throttled
+    /// (429/503) responses as the retry policy allows, and returns the body
+    /// of a successful response.
+    async fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<String, RxNormError> {
+        self.get_before(url, query, None).await
+    }
+
+    /// Like `get`, but fails with [`RxNormError::Timeout`] once `deadline` passes.
+    async fn get_before(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        deadline: Option<Instant>,
+    ) -> Result<String, RxNormError> {
+        let (body, _) = self.get_with_meta(url, query, deadline).await?;
+        Ok(body)
+    }
+
+    /// Like `get_before`, but also returns the final response's metadata.
+    pub(crate) async fn get_with_meta(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        deadline: Option<Instant>,
+    ) -> Result<(String, ResponseMeta), RxNormError> {
+        if self.fixtures.is_some() {
+            return Err(RxNormError::NoFixture(url.to_string()));
+        }
+        let result = match self.needs_version_check() {
+            true => self.check_version().await,
+            false => Ok(()),
+        };
+        let result = match result {
+            Ok(()) => self.send_before(url, query, deadline).await,
+            Err(e) => Err(e),
+        };
+        if let (Some(observer), Err(e)) = (&self.observer, &result) {
+            observer.on_error(url, e);
+        }
+        result
+    }
+
+    async fn send_before(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        deadline: Option<Instant>,
+    ) -> Result<(String, ResponseMeta), RxNormError> {
+        let request = format!("{}?{:?}", url, query);
+        let tagged = self.cache.as_ref().and_then(|cache| cache.tagged(&request));
+        let etag = tagged.as_ref().map(|(etag, _)| etag.as_str());
+        let start = Instant::now();
+        let mut attempt = 0;
+        let mut permit = None;
+        let res = loop {
+            if let Some(in_flight) = &self.in_flight {
+                permit = within(deadline, in_flight.acquire()).await?;
+            }
+            let call = self.make_call(url, query, etag);
+            let (error, retry_after, throttled) = match within(deadline, call).await? {
+                Ok(res) if is_throttled(res.status()) => {
+                    self.throttle.record_throttled();
+                    (
+                        RxNormError::UnexpectedStatus(res.status()),
+                        retry_after(res.headers()),
+                        Some(res),
+                    )
+                }
+                Ok(res) => break res,
+                Err(e) if !self.retry_policy.retries(&e) => return Err(RxNormError::from(e)),
+                Err(e) => (RxNormError::from(e), None, None),
+            };
+            attempt += 1;
+            let delay = match self
+                .retry_policy
+                .next_delay(attempt, start.elapsed(), retry_after)
+            {
+                Some(delay) => delay,
+                None => {
+                    debug!(
+                        "Request to {} with {:?} failed after {} attempts: {}",
+                        url, query, attempt, error
+                    );
+                    match throttled {
+                        Some(res) => break res,
+                        None => return Err(error),
+                    }
+                }
+            };
+            let delay = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(RxNormError::Timeout);
+                    }
+                    delay.min(remaining)
+                }
+                None => delay,
+            };
+            warn!(
+                "Request to {} with {:?} failed on attempt {}: {}, retrying in {:?}",
+                url, query, attempt, error, delay
+            );
+            self.throttle.record_retry(delay);
+            if let Some(observer) = &self.observer {
+                observer.on_retry(url, attempt, delay);
             }
+            drop(throttled);
+            drop(permit.take());
+            sleep(delay).await;
         };
         let status = res.status();
-        let body = res.text().await.unwrap();
-        if status.is_success() {
-            let rxnorm = json::parse(&body).unwrap();
-            let result: String = rxnorm["idGroup"]["rxnormId"]
-                .dump()
-                .replace(&['[', ']', '\"'][..], "");
-            if !result.eq("null") {
-                let ids: Vec<i32> = result
-                    .split(',')
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.parse().unwrap())
-                    .collect();
-                return Ok(Some(ids));
+        let new_etag = res
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let final_url = res.url().to_string();
+        let headers = header_map(res.headers());
+        let body = within(deadline, read_body(res, self.max_response_bytes)).await??;
+        drop(permit);
+        let meta = ResponseMeta {
+            status,
+            url: final_url,
+            headers,
+            elapsed: start.elapsed(),
+        };
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some((_, cached)) = tagged {
+                return Ok((cached, meta));
             }
-            return Ok(None);
-        } else {
-            Err("RxNav returned an error")
         }
+        let content_type = meta.headers.get(CONTENT_TYPE.as_str());
+        check_content(content_type.map(String::as_str), &body)?;
+        if !status.is_success() {
+            return Err(RxNormError::UnexpectedStatus(status));
+        }
+        check_api_error(&body)?;
+        if let (Some(cache), Some(etag)) = (&self.cache, new_etag) {
+            cache.insert_tagged(request, etag, body.clone());
+        }
+        Ok((body, meta))
+    }
+
+    /// Sends one request, with `If-None-Match` when `etag` is known.
+    async fn make_call(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        etag: Option<&str>,
+    ) -> Result<Response, Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let mut request = self.get_request(url, query);
+        if let Some(user_agent) = &self.user_agent {
+            request = request.header(USER_AGENT, user_agent);
+        }
+        if let Some(language) = &self.language {
+            request = request.header(ACCEPT_LANGUAGE, language.to_ascii_lowercase());
+        }
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        let request = request.build()?;
+        debug!("GET {}", request.url());
+        if let Some(observer) = &self.observer {
+            observer.on_request(url);
+        }
+        let sent = Instant::now();
+        let res = self.client.execute(request).await;
+        if let (Some(observer), Ok(res)) = (&self.observer, &res) {
+            observer.on_response(url, res.status(), sent.elapsed());
+        }
+        res
     }
 }
 
-async fn make_call(drug: &String, client: &Client, mode: &String) -> Result<Response, Error> {
-    let result = client
-        .get(RXNAV_URL)
-        .query(&[("name", &drug), ("search", &mode)])
-        .send()
-        .await;
-    result
+/// The default query parameters whose names `query` does not set itself.
+pub(crate) fn without_overridden<'a>(
+    defaults: &'a [(String, String)],
+    query: &[(&str, &str)],
+) -> Vec<(&'a str, &'a str)> {
+    defaults
+        .iter()
+        .filter(|(key, _)| !query.iter().any(|(name, _)| name == key))
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect()
+}
+
+/// The largest RxCUI that fits RxNorm's eight-character `RXCUI` column.
+const MAX_RXCUI: u32 = 99_999_999;
+
+/// Whether `id` could be an RxCUI at all: RxNorm numbers concepts from 1 and
+/// stores them in at most eight digits. A plausible id may still be unknown
+/// to RxNav; this only catches typos like `0` without a round trip.
+pub fn is_plausible_rxcui(id: u32) -> bool {
+    (1..=MAX_RXCUI).contains(&id)
+}
+
+/// Rejects an RxCUI that [`is_plausible_rxcui`] rules out.
+pub(crate) fn check_rxcui(rxcui: u32) -> Result<(), RxNormError> {
+    if !is_plausible_rxcui(rxcui) {
+        return Err(RxNormError::InvalidInput(format!(
+            "{} is not a valid RxCUI",
+            rxcui
+        )));
+    }
+    Ok(())
+}
+
+/// The path of a per-concept endpoint, such as `/rxcui/1151/properties.json`,
+/// for a plausible `rxcui`.
+pub(crate) fn rxcui_path(rxcui: u32, endpoint: &str) -> Result<String, RxNormError> {
+    check_rxcui(rxcui)?;
+    Ok(format!("/rxcui/{}/{}", rxcui, endpoint))
+}
+
+/// Joins an endpoint path onto a base URL without doubling the slash.
+pub(crate) fn join_url(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url, path.trim_start_matches('/'))
+}
+
+/// Trims a search term and collapses internal whitespace, since RxNav treats
+/// "vit c" and "vit  c" differently. Blank terms are rejected.
+pub(crate) fn normalize_term(term: &str) -> Result<String, RxNormError> {
+    let term = term.split_whitespace().collect::<Vec<_>>().join(" ");
+    if term.is_empty() {
+        return Err(RxNormError::InvalidInput(String::from(
+            "search term is empty",
+        )));
+    }
+    Ok(term)
+}
+
+/// Rejects a normalized `term` longer than `max_length` characters, or cuts
+/// it to that length with a warning when `truncate` is set.
+pub(crate) fn limit_term(
+    term: String,
+    max_length: Option<usize>,
+    truncate: bool,
+) -> Result<String, RxNormError> {
+    let max_length = match max_length {
+        Some(max_length) if term.chars().count() > max_length => max_length,
+        _ => return Ok(term),
+    };
+    if !truncate {
+        return Err(RxNormError::InvalidInput(format!(
+            "search term is longer than {} characters",
+            max_length
+        )));
+    }
+    let truncated: String = term.chars().take(max_length).collect();
+    warn!(
+        "Truncated a {} character search term to {}",
+        term.chars().count(),
+        max_length
+    );
+    Ok(truncated.trim_end().to_string())
+}
+
+/// Joins values into a single RxNav list parameter. The values are separated
+/// by spaces, which the query encoding turns into the `+` RxNav expects.
+pub(crate) fn join_list<I>(items: I) -> String
+where
+    I: IntoIterator,
+    I::Item: fmt::Display,
+{
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a normalized search that gave `result` might still be answered
+/// by an exact one: it matched nothing or failed for a reason other than the
+/// input or the deadline.
+pub(crate) fn worth_exact_search(result: &Result<Option<Vec<u32>>, RxNormError>) -> bool {
+    match result {
+        Ok(ids) => ids.is_none(),
+        Err(RxNormError::InvalidInput(_) | RxNormError::Timeout) => false,
+        Err(_) => {
+            debug!("Normalized search failed, trying an exact search");
+            true
+        }
+    }
+}
+
+/// Whether RxNav is asking us to back off: 429 Too Many Requests or 503
+/// Service Unavailable.
+pub(crate) fn is_throttled(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Reads a `Retry-After` header given in seconds. HTTP dates are not
+/// supported and fall back to the configured backoff.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Runs `future` to completion, or fails with [`RxNormError::Timeout`] if
+/// `deadline` passes first.
+async fn within<F: Future>(deadline: Option<Instant>, future: F) -> Result<F::Output, RxNormError> {
+    match deadline {
+        Some(deadline) => timeout_at(deadline, future)
+            .await
+            .map_err(|_| RxNormError::Timeout),
+        None => Ok(future.await),
+    }
+}
+
+#[derive(Deserialize)]
+struct IdResponse {
+    #[serde(rename = "idGroup", default)]
+    id_group: IdGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct IdGroup {
+    #[serde(rename = "rxnormId")]
+    rxnorm_id: Option<Vec<String>>,
+}
+
+pub(crate) fn parse_rxnorm_ids(body: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+    let response: IdResponse = parse_json(body)?;
+    match response.id_group.rxnorm_id {
+        Some(ids) if !ids.is_empty() => ids
+            .iter()
+            .map(|s| parse_rxcui(s))
+            .collect::<Result<_, _>>()
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Sorts the ids when `sort` is set, then keeps at most `max_matches`.
+pub(crate) fn tidy_ids(
+    ids: Option<Vec<u32>>,
+    sort: bool,
+    max_matches: Option<usize>,
+) -> Option<Vec<u32>> {
+    ids.map(|ids| {
+        let mut ids = if sort { sort_ids(ids) } else { ids };
+        if let Some(max_matches) = max_matches {
+            ids.truncate(max_matches);
+        }
+        ids
+    })
+}
+
+/// Sorts RxCUIs ascending and drops duplicates.
+pub(crate) fn sort_ids(mut ids: Vec<u32>) -> Vec<u32> {
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Reads a response body, failing with [`RxNormError::ResponseTooLarge`] as
+/// soon as it grows past `limit` bytes instead of buffering all of it.
+async fn read_body(mut res: Response, limit: Option<usize>) -> Result<String, RxNormError> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(res.text().await?),
+    };
+    if res.content_length().is_some_and(|len| len > limit as u64) {
+        return Err(RxNormError::ResponseTooLarge { limit });
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(RxNormError::ResponseTooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Fails with [`RxNormError::ApiError`] when a successful response carries
+/// an `error` or `errorMessage` field at the top of its JSON body, as some
+/// RxNav endpoints do instead of an error status.
+pub(crate) fn check_api_error(body: &str) -> Result<(), RxNormError> {
+    if !body.contains("\"error") {
+        return Ok(());
+    }
+    let value: serde_json::Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return Ok(()),
+    };
+    let message = ["error", "errorMessage"]
+        .iter()
+        .filter_map(|key| value.get(key))
+        .find_map(|message| match message {
+            serde_json::Value::String(message) => Some(message.clone()),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        });
+    match message {
+        Some(message) => Err(RxNormError::ApiError { message }),
+        None => Ok(()),
+    }
+}
+
+/// Characters of an unexpected body kept in
+/// [`RxNormError::UnexpectedContentType`].
+const SNIPPET_CHARS: usize = 120;
+
+/// Rejects a success response that is not JSON: a body the server calls
+/// HTML or that starts like an HTML page, or one labelled JSON that starts
+/// like neither JSON nor markup. Other bodies, such as XML responses, pass.
+pub(crate) fn check_content(content_type: Option<&str>, body: &str) -> Result<(), RxNormError> {
+    let start = body.trim_start();
+    let declared = content_type.unwrap_or_default().to_ascii_lowercase();
+    let starts_with = |prefix: &str| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+    };
+    let is_html =
+        declared.contains("html") || starts_with("<!doctype html") || starts_with("<html");
+    let is_mislabelled =
+        declared.contains("json") && !start.is_empty() && !start.starts_with(['{', '[', '<']);
+    if !is_html && !is_mislabelled {
+        return Ok(());
+    }
+    let words: Vec<&str> = start.split_whitespace().collect();
+    Err(RxNormError::UnexpectedContentType {
+        content_type: content_type.map(str::to_string),
+        snippet: words.join(" ").chars().take(SNIPPET_CHARS).collect(),
+    })
+}
+
+/// Deserializes an RxNav response body into the given wire type.
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &str) -> Result<T, RxNormError> {
+    serde_json::from_str(body).map_err(|e| RxNormError::JsonParse(e.to_string()))
+}
+
+/// Parses an RxCUI that RxNav sent as a string. RxCUIs are unsigned, so a
+/// negative id is reported as an [`RxNormError::IdParse`].
+pub(crate) fn parse_rxcui(id: &str) -> Result<u32, RxNormError> {
+    id.trim()
+        .parse()
+        .map_err(|_| RxNormError::IdParse(id.to_string()))
+}
+
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::net::SocketAddr;
+
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Builds a raw HTTP/1.1 response with a JSON body.
+    pub(crate) fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\n", status);
+        for header in headers {
+            response.push_str(header);
+            response.push_str("\r\n");
+        }
+        response.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        response
+    }
+
+    /// Serves the given responses, one per connection, in order.
+    pub(crate) async fn serve(responses: Vec<String>) -> SocketAddr {
+        serve_recording(responses).await.0
+    }
+
+    /// Like `serve`, but also sends out the request line of each request,
+    /// e.g. `GET /rxcui.json?name=aspirin HTTP/1.1`.
+    pub(crate) async fn serve_recording(responses: Vec<String>) -> (SocketAddr, Receiver<String>) {
+        serve_delayed(responses.into_iter().map(|r| (Duration::ZERO, r)).collect()).await
+    }
+
+    /// Like `serve_recording`, but waits the given time before sending each
+    /// response, e.g. to outlast a client timeout. Connections keep being
+    /// accepted while a response waits.
+    pub(crate) async fn serve_delayed(
+        responses: Vec<(Duration, String)>,
+    ) -> (SocketAddr, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = channel();
+        tokio::spawn(async move {
+            for (delay, response) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let _ = sender.send(request.lines().next().unwrap_or_default().to_string());
+                let reply = async move {
+                    tokio::time::sleep(delay).await;
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                };
+                if delay.is_zero() {
+                    reply.await;
+                } else {
+                    tokio::spawn(reply);
+                }
+            }
+        });
+        (addr, receiver)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[tokio::test]
     async fn test_vit_c_with_normalizer() {
         let http_client = reqwest::Client::new();
         let rx_client = RxNormClient::new(http_client, true);
         let vit_c: &String = &String::from("vit-c");
-        let expected: Vec<i32> = vec![1088438, 1151];
-        let actual: Vec<i32> = rx_client
-            .find_rxcui(&vit_c)
+        let expected: Vec<u32> = vec![1088438, 1151];
+        let actual: Vec<u32> = rx_client
+            .find_rxcui(vit_c)
             .await
             .unwrap()
             .expect("Something went wrong");
@@ -110,7 +1036,776 @@ mod tests {
         let http_client = reqwest::Client::new();
         let rx_client = RxNormClient::new(http_client, false);
         let vit_c: &String = &String::from("vit-c");
-        let actual: Option<Vec<i32>> = rx_client.find_rxcui(&vit_c).await.unwrap();
+        let actual: Option<Vec<u32>> = rx_client.find_rxcui(vit_c).await.unwrap();
         assert!(actual.is_none());
     }
+
+    #[test]
+    fn test_base_url_is_joined() {
+        let rx_client = RxNormClient::with_base_url(
+            reqwest::Client::new(),
+            true,
+            "http://localhost:4000/REST/",
+        );
+        assert_eq!(
+            "http://localhost:4000/REST/rxcui.json",
+            rx_client.url("/rxcui.json")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_return_error() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(1))
+            .build();
+        let actual = rx_client.find_rxcui("vit-c").await;
+        assert!(matches!(actual, Err(RxNormError::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn test_find_rxcui_outcomes() {
+        let addr = test_server::serve(vec![
+            test_server::response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+            test_server::response("200 OK", &[], r#"{"idGroup":{"name":"unobtainium"}}"#),
+            test_server::response("200 OK", &[], r#"{"idGroup":{"rxnormId":[]}}"#),
+            test_server::response("404 Not Found", &[], "{}"),
+            test_server::response("500 Internal Server Error", &[], "{}"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        assert_eq!(
+            Some(vec![1151]),
+            rx_client.find_rxcui("vit-c").await.unwrap()
+        );
+        assert_eq!(None, rx_client.find_rxcui("unobtainium").await.unwrap());
+        assert_eq!(None, rx_client.find_rxcui("unobtainium").await.unwrap());
+        for status in [StatusCode::NOT_FOUND, StatusCode::INTERNAL_SERVER_ERROR] {
+            assert!(matches!(
+                rx_client.find_rxcui("vit-c").await,
+                Err(RxNormError::UnexpectedStatus(s)) if s == status
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_fails_without_sleeping() {
+        let (addr, requests) = test_server::serve_recording(vec![test_server::response(
+            "503 Service Unavailable",
+            &[],
+            "",
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .retry_backoff(Duration::from_secs(30))
+            .no_retry()
+            .build();
+        let start = Instant::now();
+        assert!(matches!(
+            rx_client.find_rxcui("vit-c").await,
+            Err(RxNormError::UnexpectedStatus(
+                StatusCode::SERVICE_UNAVAILABLE
+            ))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(1, requests.try_iter().count());
+        assert_eq!(0, rx_client.throttle_stats().retry_count);
+    }
+
+    #[tokio::test]
+    async fn test_hung_request_times_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            sleep(Duration::from_secs(10)).await;
+        });
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .timeout(Duration::from_millis(50))
+            .build();
+        let actual = rx_client.find_rxcui("vit-c").await;
+        assert!(matches!(actual, Err(RxNormError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cuts_retry_backoff_short() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(3)
+            .retry_backoff(Duration::from_secs(30))
+            .build();
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(100);
+        let actual = rx_client.find_rxcui_with_deadline("vit-c", deadline).await;
+        assert!(matches!(actual, Err(RxNormError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .retry_policy(RetryPolicy::fixed(3, Duration::from_secs(10)).should_retry(|_| false))
+            .build();
+        let start = Instant::now();
+        let actual = rx_client.find_rxcui("vit-c").await;
+        assert!(matches!(actual, Err(RxNormError::Http(_))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_is_retried() {
+        let addr = test_server::serve(vec![
+            test_server::response("429 Too Many Requests", &["Retry-After: 0"], ""),
+            test_server::response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .retry_backoff(Duration::from_secs(30))
+            .build();
+        let actual = rx_client.find_rxcui("vit-c").await.unwrap();
+        assert_eq!(Some(vec![1151]), actual);
+    }
+
+    #[tokio::test]
+    async fn test_throttle_stats_count_throttling_and_retries() {
+        let addr = test_server::serve(vec![
+            test_server::response("429 Too Many Requests", &[], ""),
+            test_server::response("503 Service Unavailable", &[], ""),
+            test_server::response("500 Internal Server Error", &[], "{}"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(1)
+            .retry_backoff(Duration::from_millis(10))
+            .build();
+        assert_eq!(ThrottleStats::default(), rx_client.throttle_stats());
+        assert!(rx_client.find_rxcui("vit-c").await.is_err());
+        assert!(rx_client.clone().find_rxcui("vit-c").await.is_err());
+        let stats = rx_client.throttle_stats();
+        assert_eq!(2, stats.rate_limited_count);
+        assert_eq!(1, stats.retry_count);
+        assert_eq!(Duration::from_millis(10), stats.total_wait_time);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_without_retries_left() {
+        let addr = test_server::serve(vec![test_server::response(
+            "429 Too Many Requests",
+            &[],
+            "",
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let actual = rx_client.find_rxcui("vit-c").await;
+        assert!(matches!(
+            actual,
+            Err(RxNormError::UnexpectedStatus(StatusCode::TOO_MANY_REQUESTS))
+        ));
+    }
+
+    #[test]
+    fn test_parse_malformed_json() {
+        let actual = parse_rxnorm_ids("{\"idGroup\":");
+        assert!(matches!(actual, Err(RxNormError::JsonParse(_))));
+    }
+
+    #[test]
+    fn test_parse_negative_id() {
+        let actual = parse_rxnorm_ids(r#"{"idGroup":{"rxnormId":["-1151"]}}"#);
+        assert!(matches!(actual, Err(RxNormError::IdParse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_implausible_rxcui_is_rejected_without_request() {
+        assert!(is_plausible_rxcui(1151));
+        assert!(!is_plausible_rxcui(0));
+        assert!(!is_plausible_rxcui(u32::MAX));
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        assert!(matches!(
+            rx_client.get_properties(0).await,
+            Err(RxNormError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            rx_client.find_interactions(100_000_000).await,
+            Err(RxNormError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_non_numeric_id() {
+        let actual = parse_rxnorm_ids(r#"{"idGroup":{"rxnormId":["1151","abc"]}}"#);
+        match actual {
+            Err(RxNormError::IdParse(id)) => assert_eq!("abc", id),
+            other => panic!("expected IdParse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_normalize_term_collapses_whitespace() {
+        assert_eq!("vit c", normalize_term("  vit \t c ").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_blank_term_is_rejected_without_request() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let actual = rx_client.find_rxcui("   ").await;
+        assert!(matches!(actual, Err(RxNormError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_overlong_term_is_rejected_without_request() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .build();
+        let sig = "take one tablet by mouth ".repeat(20);
+        let actual = rx_client.find_rxcui(&sig).await;
+        assert!(matches!(actual, Err(RxNormError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_limit_term_truncates_on_request() {
+        let term = String::from("acetaminophen 325 MG");
+        assert_eq!(term, limit_term(term.clone(), Some(20), false).unwrap());
+        assert_eq!(term, limit_term(term.clone(), None, false).unwrap());
+        assert_eq!(
+            "acetaminophen",
+            limit_term(term.clone(), Some(14), true).unwrap()
+        );
+        assert!(limit_term(term, Some(14), false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_mode_on_id_search_is_rejected_without_request() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .default_query_param("search", "1")
+            .build();
+        let actual = rx_client.find_rxcui_by_id("N02BE01", IdType::Atc).await;
+        assert!(matches!(actual, Err(RxNormError::InvalidInput(msg)) if msg.contains("idtype")));
+    }
+
+    #[tokio::test]
+    async fn test_call_endpoint_returns_raw_json() {
+        let body = r#"{"version":"07-Oct-2024"}"#;
+        let addr = test_server::serve(vec![test_server::response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST/", addr))
+            .build();
+        let json = rx_client.call_endpoint("/version.json", &[]).await.unwrap();
+        assert_eq!("07-Oct-2024", json["version"]);
+    }
+
+    #[test]
+    fn test_error_payload_in_success_body() {
+        let error = check_api_error(r#"{"error":"Invalid rxcui"}"#).unwrap_err();
+        assert!(matches!(error, RxNormError::ApiError { message } if message == "Invalid rxcui"));
+        assert!(check_api_error(r#"{"idGroup":{"name":"error"}}"#).is_ok());
+        assert!(check_api_error(r#"{"error":null}"#).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_is_rejected() {
+        let body = format!(
+            r#"{{"termTypeList":{{"termType":["{}"]}}}}"#,
+            "X".repeat(64)
+        );
+        let addr = test_server::serve(vec![test_server::response("200 OK", &[], &body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_response_bytes(32)
+            .build();
+        let result = rx_client.get_term_types().await;
+        assert!(matches!(
+            result,
+            Err(RxNormError::ResponseTooLarge { limit: 32 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_special_characters_in_names_are_encoded() {
+        let (addr, requests) = test_server::serve_recording(vec![test_server::response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["10180"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .search_mode(SearchMode::Exact)
+            .build();
+        let drug = "sulfamethoxazole & trimethoprim+x #1 100%";
+        assert_eq!(Some(vec![10180]), rx_client.find_rxcui(drug).await.unwrap());
+        assert_eq!(
+            "GET /rxcui.json?name=sulfamethoxazole+%26+trimethoprim%2Bx+%231+100%25&search=0 HTTP/1.1",
+            requests.recv().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sort_ids_drops_duplicates() {
+        assert_eq!(vec![1151, 1088438], sort_ids(vec![1088438, 1151, 1088438]));
+    }
+
+    /// A body parser, reduced to whether it succeeded.
+    type Parser = fn(&str) -> Result<(), RxNormError>;
+
+    /// Every body parser, by name.
+    fn parsers() -> Vec<(&'static str, Parser)> {
+        vec![
+            ("rxnorm_ids", |b| parse_rxnorm_ids(b).map(drop)),
+            ("candidates", |b| parse_candidates(b).map(drop)),
+            ("min_concepts", |b| {
+                crate::concepts::parse_min_concepts(b).map(drop)
+            }),
+            ("display_terms", |b| {
+                crate::display::parse_display_terms(b).map(drop)
+            }),
+            ("brand_group", |b| {
+                crate::drugs::parse_brand_group(b).map(drop)
+            }),
+            ("drug_group", |b| {
+                crate::drugs::parse_drug_group(b).map(drop)
+            }),
+            ("history_status", |b| {
+                crate::history::parse_history_status(b).map(drop)
+            }),
+            ("interactions", |b| {
+                crate::interaction::parse_interactions(b).map(drop)
+            }),
+            ("interaction_list", |b| {
+                crate::interaction::parse_interaction_list(b).map(drop)
+            }),
+            ("ndc_properties", |b| {
+                crate::ndc::parse_ndc_properties(b).map(drop)
+            }),
+            ("historical_ndcs", |b| {
+                crate::ndc::parse_historical_ndcs(b).map(drop)
+            }),
+            ("properties", |b| {
+                crate::properties::parse_properties(b).map(drop)
+            }),
+            ("prop_concepts", |b| {
+                crate::properties::parse_prop_concepts(b).map(drop)
+            }),
+            ("filtered_rxcui", |b| {
+                crate::properties::parse_filtered_rxcui(b).map(drop)
+            }),
+            ("related_group", |b| {
+                crate::related::parse_related_group(b).map(drop)
+            }),
+            ("all_related_group", |b| {
+                crate::related::parse_all_related_group(b).map(drop)
+            }),
+            ("class_members", |b| {
+                crate::rxclass::parse_class_members(b).map(drop)
+            }),
+            ("atc_classes", |b| {
+                crate::rxclass::parse_atc_classes(b).map(drop)
+            }),
+            ("rxterms", |b| crate::rxterms::parse_rxterms(b).map(drop)),
+            ("suggestions", |b| {
+                crate::spelling::parse_suggestions(b).map(drop)
+            }),
+            ("term_types", |b| {
+                crate::term_type::parse_term_types(b).map(drop)
+            }),
+            ("version", |b| crate::version::parse_version(b).map(drop)),
+        ]
+    }
+
+    #[test]
+    fn test_non_json_bodies_are_parse_errors() {
+        for (name, parse) in parsers() {
+            for body in [
+                "",
+                "   ",
+                "<html>Bad Gateway</html>",
+                "{\"idGroup\":",
+                "\u{0}",
+            ] {
+                assert!(
+                    matches!(parse(body), Err(RxNormError::JsonParse(_))),
+                    "{} accepted {:?}",
+                    name,
+                    body
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unexpected_shapes_never_panic() {
+        let bodies = [
+            "null",
+            "[]",
+            "42",
+            "\"text\"",
+            "{}",
+            r#"{"idGroup":"oops"}"#,
+            r#"{"idGroup":{"rxnormId":"1151"}}"#,
+            r#"{"relatedGroup":{"conceptGroup":[{"conceptProperties":[{}]}]}}"#,
+            r#"{"drugGroup":{"conceptGroup":null}}"#,
+            r#"{"properties":[]}"#,
+            r#"{"rxtermsProperties":{"rxcui":1}}"#,
+        ];
+        for (name, parse) in parsers() {
+            for body in bodies {
+                match std::panic::catch_unwind(|| parse(body)) {
+                    Ok(Ok(()))
+                    | Ok(Err(RxNormError::JsonParse(_)))
+                    | Ok(Err(RxNormError::IdParse(_))) => {}
+                    Ok(Err(e)) => panic!("{} gave {:?} for {:?}", name, e, body),
+                    Err(_) => panic!("{} panicked on {:?}", name, body),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_numeric_ids_are_id_errors() {
+        let cases: [(&str, Parser, &str); 4] = [
+            (
+                "rxnorm_ids",
+                |b| parse_rxnorm_ids(b).map(drop),
+                r#"{"idGroup":{"rxnormId":["abc"]}}"#,
+            ),
+            (
+                "rxnorm_ids",
+                |b| parse_rxnorm_ids(b).map(drop),
+                r#"{"idGroup":{"rxnormId":["-5"]}}"#,
+            ),
+            (
+                "related_group",
+                |b| crate::related::parse_related_group(b).map(drop),
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[{"rxcui":"x1"}]}]}}"#,
+            ),
+            (
+                "filtered_rxcui",
+                |b| crate::properties::parse_filtered_rxcui(b).map(drop),
+                r#"{"rxcui":"99999999999"}"#,
+            ),
+        ];
+        for (name, parse, body) in cases {
+            assert!(
+                matches!(parse(body), Err(RxNormError::IdParse(_))),
+                "{} accepted {}",
+                name,
+                body
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_html_success_response_is_an_unexpected_content_type() {
+        let addr = test_server::serve(vec![test_server::response(
+            "200 OK",
+            &[],
+            "<html><body>Service temporarily unavailable</body></html>",
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        match rx_client.find_rxcui("aspirin").await {
+            Err(RxNormError::UnexpectedContentType {
+                content_type,
+                snippet,
+            }) => {
+                assert_eq!(Some("application/json"), content_type.as_deref());
+                assert!(snippet.starts_with("<html><body>Service temporarily"));
+            }
+            other => panic!("expected UnexpectedContentType, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_html_error_response_is_an_unexpected_content_type() {
+        let addr = test_server::serve(vec![test_server::response(
+            "503 Service Unavailable",
+            &["Content-Type: text/html"],
+            "<html><body>Down for maintenance</body></html>",
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        match rx_client.find_rxcui("aspirin").await {
+            Err(RxNormError::UnexpectedContentType { snippet, .. }) => {
+                assert!(snippet.starts_with("<html><body>Down for maintenance"));
+            }
+            other => panic!("expected UnexpectedContentType, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_content_tells_html_from_json_and_xml() {
+        assert!(check_content(Some("application/json"), r#"{"idGroup":{}}"#).is_ok());
+        assert!(check_content(
+            Some("application/xml"),
+            "<?xml version=\"1.0\"?><rxnormdata/>"
+        )
+        .is_ok());
+        assert!(check_content(None, "").is_ok());
+        assert!(matches!(
+            check_content(Some("text/html; charset=UTF-8"), "Down for maintenance"),
+            Err(RxNormError::UnexpectedContentType { .. })
+        ));
+        assert!(matches!(
+            check_content(None, "\n  <!DOCTYPE html><html></html>"),
+            Err(RxNormError::UnexpectedContentType {
+                content_type: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_max_matches_truncates_after_sorting() {
+        let ids = Some(vec![1088438, 1151, 1088438, 200]);
+        assert_eq!(Some(vec![200, 1151]), tidy_ids(ids.clone(), true, Some(2)));
+        assert_eq!(Some(vec![1088438]), tidy_ids(ids.clone(), false, Some(1)));
+        assert_eq!(ids, tidy_ids(ids.clone(), false, None));
+        assert_eq!(None, tidy_ids(None, true, Some(2)));
+    }
+
+    #[test]
+    fn test_user_agent_defaults_only_for_own_client() {
+        let own = RxNormClient::builder().build();
+        assert_eq!(Some(DEFAULT_USER_AGENT), own.user_agent.as_deref());
+        let shared = RxNormClient::new(reqwest::Client::new(), true);
+        assert_eq!(None, shared.user_agent);
+        let custom = RxNormClient::builder()
+            .http_client(reqwest::Client::new())
+            .user_agent("my-app/1.0")
+            .build();
+        assert_eq!(Some("my-app/1.0"), custom.user_agent.as_deref());
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_reuses_tagged_body() {
+        let addr = test_server::serve(vec![
+            test_server::response(
+                "200 OK",
+                &["ETag: \"v1\""],
+                r#"{"termTypeList":{"termType":["BN","IN"]}}"#,
+            ),
+            test_server::response("304 Not Modified", &[], ""),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build()
+            .with_cache(4);
+        let first = rx_client.get_term_types().await.unwrap();
+        let second = rx_client.get_term_types().await.unwrap();
+        assert_eq!(vec!["BN", "IN"], first);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_default_query_params_yield_to_endpoint_params() {
+        let (addr, requests) = test_server::serve_recording(vec![test_server::response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["1151"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .search_mode(SearchMode::Exact)
+            .default_query_param("apiKey", "secret")
+            .default_query_param("name", "ignored")
+            .build();
+        rx_client.find_rxcui("vitamin c").await.unwrap();
+        assert_eq!(
+            "GET /rxcui.json?name=vitamin+c&search=0&apiKey=secret HTTP/1.1",
+            requests.recv().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_url_matches_sent_url() {
+        let rx_client = RxNormClient::builder()
+            .base_url("http://localhost:4000/REST")
+            .default_query_param("apiKey", "secret")
+            .default_query_param("name", "ignored")
+            .build();
+        let url = rx_client
+            .build_url("rxcui.json", &[("name", "Tylenol & Co"), ("search", "0")])
+            .unwrap();
+        assert_eq!(
+            "http://localhost:4000/REST/rxcui.json?name=Tylenol+%26+Co&search=0&apiKey=secret",
+            url.as_str()
+        );
+        let broken = RxNormClient::builder().base_url("not a url").build();
+        assert!(broken.build_url("/rxcui.json", &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_supplied_client_redirect_policy_is_kept() {
+        let addr = test_server::serve(vec![test_server::response(
+            "302 Found",
+            &["Location: https://example.invalid/REST/version.json"],
+            "",
+        )])
+        .await;
+        let http_client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let rx_client = RxNormClient::builder()
+            .http_client(http_client)
+            .base_url(format!("http://{}", addr))
+            .build();
+        let actual = rx_client.get_version().await;
+        assert!(matches!(
+            actual,
+            Err(RxNormError::UnexpectedStatus(StatusCode::FOUND))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_cache() {
+        let addr = test_server::serve(vec![test_server::response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["1151"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .build()
+            .with_cache(4);
+        let clone = rx_client.clone();
+        rx_client.find_rxcui("vit-c").await.unwrap();
+        assert_eq!(Some(vec![1151]), clone.find_rxcui("vit-c").await.unwrap());
+        assert_eq!(1, rx_client.cache_stats().hits);
+    }
+
+    #[tokio::test]
+    async fn test_cache_ignores_case_but_sends_name_as_given() {
+        let (addr, requests) = test_server::serve_recording(vec![test_server::response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["1191"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .build()
+            .with_cache(4);
+        rx_client.find_rxcui("Aspirin").await.unwrap();
+        assert_eq!(
+            Some(vec![1191]),
+            rx_client.find_rxcui(" ASPIRIN ").await.unwrap()
+        );
+        assert_eq!(1, rx_client.cache_stats().hits);
+        let sent: Vec<String> = requests.try_iter().collect();
+        assert_eq!(1, sent.len());
+        assert!(sent[0].contains("name=Aspirin"), "{}", sent[0]);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_permit_is_returned_after_the_body() {
+        let addr = test_server::serve(vec![test_server::response(
+            "200 OK",
+            &[],
+            r#"{"idGroup":{"rxnormId":["1151"]}}"#,
+        )])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_in_flight(1)
+            .max_requests_per_second(10)
+            .build();
+        assert_eq!(Some(0), rx_client.inflight_permits());
+        rx_client.find_rxcui("vit-c").await.unwrap();
+        assert_eq!(Some(0), rx_client.inflight_permits());
+        assert!(rx_client.rate_limit_delay().is_some());
+        let unlimited = RxNormClient::builder().build();
+        assert_eq!(None, unlimited.inflight_permits());
+        assert_eq!(None, unlimited.rate_limit_delay());
+    }
+
+    #[derive(Default)]
+    struct Counts {
+        requests: AtomicU32,
+        responses: AtomicU32,
+        errors: AtomicU32,
+        retries: AtomicU32,
+    }
+
+    impl RequestObserver for Counts {
+        fn on_request(&self, _url: &str) {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_response(&self, _url: &str, _status: StatusCode, _latency: Duration) {
+            self.responses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_error(&self, _url: &str, _error: &RxNormError) {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_retry(&self, _url: &str, _attempt: u32, _delay: Duration) {
+            self.retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_retried_request() {
+        let addr = test_server::serve(vec![
+            test_server::response("429 Too Many Requests", &["Retry-After: 0"], ""),
+            test_server::response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+        ])
+        .await;
+        let counts = Arc::new(Counts::default());
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .observer(counts.clone())
+            .build();
+        rx_client.find_rxcui("vit-c").await.unwrap();
+        assert_eq!(2, counts.requests.load(Ordering::Relaxed));
+        assert_eq!(2, counts.responses.load(Ordering::Relaxed));
+        assert_eq!(1, counts.retries.load(Ordering::Relaxed));
+        assert_eq!(0, counts.errors.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_final_error() {
+        let counts = Arc::new(Counts::default());
+        let rx_client = RxNormClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .max_retries(0)
+            .observer(counts.clone())
+            .build();
+        assert!(rx_client.find_rxcui("vit-c").await.is_err());
+        assert_eq!(1, counts.errors.load(Ordering::Relaxed));
+        assert_eq!(0, counts.responses.load(Ordering::Relaxed));
+    }
 }
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+
+/// An in-memory fuzzy index over drug names, usually the list from
+/// [`get_display_terms`](crate::RxNormClient::get_display_terms), for
+/// autocomplete without a request per keystroke.
+///
+/// Names are compared by the character pairs they share (the Sørensen–Dice
+/// coefficient), ignoring case, so small typos and partial words still
+/// score well. Once built the matcher needs no client.
+///
+/// # Examples
+/// ```rust
+/// use rxnormalizer::LocalMatcher;
+///
+/// let matcher = LocalMatcher::from_display_terms(vec![
+///     String::from("acetaminophen"),
+///     String::from("aspirin"),
+/// ]);
+/// let matches = matcher.best_matches("acetaminofen", 1);
+/// assert_eq!("acetaminophen", matches[0].0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LocalMatcher {
+    terms: Vec<(String, Vec<(char, char)>)>,
+}
+
+impl LocalMatcher {
+    /// Indexes `terms`. Blank terms are left out.
+    pub fn from_display_terms(terms: Vec<String>) -> Self {
+        let terms = terms
+            .into_iter()
+            .filter(|term| !term.trim().is_empty())
+            .map(|term| {
+                let bigrams = bigrams(&term);
+                (term, bigrams)
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// The `n` terms most similar to `query`, best first, each with a score
+    /// from just above 0 to 1 for an exact match. Terms sharing nothing with
+    /// `query` are never returned, and ties keep the indexed order.
+    pub fn best_matches(&self, query: &str, n: usize) -> Vec<(String, f32)> {
+        let query = bigrams(query);
+        if query.is_empty() || n == 0 {
+            return Vec::new();
+        }
+        let mut scored: Vec<(&str, f32)> = self
+            .terms
+            .iter()
+            .map(|(term, bigrams)| (term.as_str(), dice(&query, bigrams)))
+            .filter(|&(_, score)| score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        scored
+            .into_iter()
+            .take(n)
+            .map(|(term, score)| (term.to_string(), score))
+            .collect()
+    }
+
+    /// The number of indexed terms.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// How alike two names are, from 0 for nothing in common to 1 for the same
+/// name, scored the way [`LocalMatcher::best_matches`] scores them.
+pub(crate) fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    dice(&a, &b)
+}
+
+/// The sorted character pairs of `text`, lowercased with whitespace
+/// collapsed and padded at both ends so a single letter still has pairs.
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let padded: Vec<char> = format!(" {} ", words.join(" ")).chars().collect();
+    let mut pairs: Vec<(char, char)> = padded.windows(2).map(|w| (w[0], w[1])).collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// The Sørensen–Dice coefficient of two sorted bigram lists, counting
+/// repeated pairs as often as they occur in both.
+fn dice(a: &[(char, char)], b: &[(char, char)]) -> f32 {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while let (Some(x), Some(y)) = (a.get(i), b.get(j)) {
+        match x.cmp(y) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    (2 * shared) as f32 / (a.len() + b.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher() -> LocalMatcher {
+        LocalMatcher::from_display_terms(
+            [
+                "acetaminophen",
+                "Aspirin",
+                "amoxicillin",
+                "  ",
+                "aspirin / caffeine",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_exact_match_scores_one() {
+        let matches = matcher().best_matches("ASPIRIN", 2);
+        assert_eq!(("Aspirin".to_string(), 1.0), matches[0]);
+        assert_eq!("aspirin / caffeine", matches[1].0);
+    }
+
+    #[test]
+    fn test_typos_still_match() {
+        let matches = matcher().best_matches("amoxicilin", 1);
+        assert_eq!("amoxicillin", matches[0].0);
+        assert!(matches[0].1 > 0.8);
+    }
+
+    #[test]
+    fn test_nothing_shared_gives_nothing() {
+        let matcher = matcher();
+        assert_eq!(4, matcher.len());
+        assert!(matcher.best_matches("zzz", 5).is_empty());
+        assert!(matcher.best_matches("   ", 5).is_empty());
+        assert!(matcher.best_matches("aspirin", 0).is_empty());
+    }
+
+    #[test]
+    fn test_similarity_ignores_case_and_spacing() {
+        assert_eq!(1.0, similarity("Ascorbic  Acid", "ascorbic acid"));
+        assert!(similarity("ascorbic acid", "ascorbic acid 500 MG") > 0.7);
+        assert!(similarity("ascorbic acid", "warfarin") < 0.3);
+        assert_eq!(0.0, similarity("", ""));
+    }
+}
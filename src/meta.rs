@@ -0,0 +1,82 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{parse_rxnorm_ids, RxNormClient, RxNormError};
+
+/// What came back with the final response to a request, for diagnosing
+/// throttling and slow calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// The status of the final response, after any retries.
+    pub status: StatusCode,
+    /// The URL the response came from, after any redirects.
+    pub url: String,
+    /// The response headers, keyed by lowercase name. Headers that are not
+    /// valid text are left out.
+    pub headers: HashMap<String, String>,
+    /// How long the request took, including retries and reading the body.
+    pub elapsed: Duration,
+}
+
+impl RxNormClient {
+    /// Like [`find_rxcui`](Self::find_rxcui), but also returns the response
+    /// metadata. The lookup always goes to the server: the cache and the
+    /// approximate fallback are not used.
+    pub async fn find_rxcui_with_meta(
+        &self,
+        drug: &str,
+    ) -> Result<(Option<Vec<u32>>, ResponseMeta), RxNormError> {
+        let drug = self.prepare_term(drug)?;
+        let url = self.url("/rxcui.json");
+        let query = self.scoped(&[("name", &drug), ("search", self.search_mode.as_param())])?;
+        let (body, meta) = self.get_with_meta(&url, &query, None).await?;
+        Ok((self.tidy_ids(parse_rxnorm_ids(&body)?), meta))
+    }
+}
+
+pub(crate) fn header_map(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+
+    #[tokio::test]
+    async fn test_meta_reports_final_response() {
+        let addr = test_server::serve(vec![
+            test_server::response("429 Too Many Requests", &["Retry-After: 0"], ""),
+            test_server::response(
+                "200 OK",
+                &["X-RateLimit-Remaining: 19"],
+                r#"{"idGroup":{"rxnormId":["1151"]}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(1)
+            .build();
+        let (ids, meta) = rx_client.find_rxcui_with_meta("vitamin c").await.unwrap();
+        assert_eq!(Some(vec![1151]), ids);
+        assert_eq!(StatusCode::OK, meta.status);
+        assert!(meta
+            .url
+            .starts_with(&format!("http://{}/rxcui.json?", addr)));
+        assert_eq!(
+            Some("19"),
+            meta.headers
+                .get("x-ratelimit-remaining")
+                .map(String::as_str)
+        );
+    }
+}
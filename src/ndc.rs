@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parse_json, parse_rxcui, rxcui_path, IdType, RxConceptProperties, RxNormClient, RxNormError,
+};
+
+/// A period during which an NDC was associated with a concept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoricalNdc {
+    pub ndc: String,
+    /// First month of the association, as `YYYYMM`.
+    pub start_date: String,
+    /// Last month of the association, as `YYYYMM`, if RxNav reports one.
+    pub end_date: Option<String>,
+}
+
+/// What RxNav knows about an NDC, as returned by [`RxNormClient::get_ndc_properties`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NdcProperties {
+    /// The NDC in its 11-digit form.
+    pub ndc11: String,
+    /// The marketing status, e.g. `ACTIVE`, if RxNav reports one.
+    pub status: Option<String>,
+    /// The concept the NDC belongs to.
+    pub rxcui: u32,
+    /// Package descriptions, e.g. `30 TABLET in 1 BOTTLE`.
+    pub packaging: Vec<String>,
+}
+
+impl RxNormClient {
+    /// Finds the RxCUIs associated with a National Drug Code.
+    ///
+    /// Both hyphenated (`0781-1506-10`) and 11-digit (`00781150610`) NDCs are accepted.
+    pub async fn find_rxcui_by_ndc(&self, ndc: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        self.find_rxcui_by_id(&normalize_ndc(ndc), IdType::Ndc)
+            .await
+    }
+
+    /// Finds the concept an NDC belongs to and returns its properties, such
+    /// as its name and term type, or `None` if RxNav does not know the NDC.
+    ///
+    /// An NDC RxNav associates with several RxCUIs gives the properties of
+    /// the first; [`resolve_ndc_all`](Self::resolve_ndc_all) gives them all.
+    pub async fn resolve_ndc(&self, ndc: &str) -> Result<Option<RxConceptProperties>, RxNormError> {
+        match self.find_rxcui_by_ndc(ndc).await? {
+            Some(rxcuis) if !rxcuis.is_empty() => self.get_properties(rxcuis[0]).await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Like [`resolve_ndc`](Self::resolve_ndc), but returns the properties of
+    /// every RxCUI the NDC is associated with, in RxNav's order, at one
+    /// request per RxCUI. An unknown NDC gives an empty vec.
+    pub async fn resolve_ndc_all(
+        &self,
+        ndc: &str,
+    ) -> Result<Vec<RxConceptProperties>, RxNormError> {
+        let mut concepts = Vec::new();
+        for rxcui in self.find_rxcui_by_ndc(ndc).await?.unwrap_or_default() {
+            if let Some(props) = self.get_properties(rxcui).await? {
+                concepts.push(props);
+            }
+        }
+        Ok(concepts)
+    }
+
+    /// Lists every NDC ever associated with a concept, including ones that
+    /// are no longer current, with the months they were valid.
+    pub async fn get_historical_ndcs(&self, rxcui: u32) -> Result<Vec<HistoricalNdc>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "allhistoricalndcs.json")?);
+        let body = self.get(&url, &[]).await?;
+        parse_historical_ndcs(&body)
+    }
+
+    /// Looks up the status, packaging and concept of an NDC, or `None` if
+    /// RxNav does not know it. Any NDC form RxNav takes is accepted.
+    pub async fn get_ndc_properties(
+        &self,
+        ndc: &str,
+    ) -> Result<Option<NdcProperties>, RxNormError> {
+        let url = self.url("/ndcproperties.json");
+        let body = self.get(&url, &[("id", ndc.trim())]).await?;
+        parse_ndc_properties(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct NdcPropertiesResponse {
+    #[serde(rename = "ndcPropertyList")]
+    ndc_property_list: Option<NdcPropertyList>,
+}
+
+#[derive(Deserialize)]
+struct NdcPropertyList {
+    #[serde(rename = "ndcProperty", default)]
+    ndc_property: Vec<NdcProperty>,
+}
+
+#[derive(Deserialize)]
+struct NdcProperty {
+    #[serde(rename = "ndcItem", default)]
+    ndc_item: String,
+    rxcui: String,
+    #[serde(rename = "packagingList")]
+    packaging_list: Option<PackagingList>,
+    #[serde(rename = "propertyConceptList")]
+    property_concept_list: Option<PropertyConceptList>,
+}
+
+#[derive(Deserialize)]
+struct PackagingList {
+    #[serde(default)]
+    packaging: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PropertyConceptList {
+    #[serde(rename = "propertyConcept", default)]
+    property_concept: Vec<PropertyConcept>,
+}
+
+#[derive(Deserialize)]
+struct PropertyConcept {
+    #[serde(rename = "propName", default)]
+    prop_name: String,
+    #[serde(rename = "propValue", default)]
+    prop_value: String,
+}
+
+/// The first NDC of an `ndcproperties.json` response.
+pub(crate) fn parse_ndc_properties(body: &str) -> Result<Option<NdcProperties>, RxNormError> {
+    let response: NdcPropertiesResponse = parse_json(body)?;
+    let property = match response
+        .ndc_property_list
+        .and_then(|list| list.ndc_property.into_iter().next())
+    {
+        Some(property) => property,
+        None => return Ok(None),
+    };
+    let status = property
+        .property_concept_list
+        .map(|list| list.property_concept)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|concept| concept.prop_name == "MARKETING_STATUS")
+        .map(|concept| concept.prop_value);
+    Ok(Some(NdcProperties {
+        ndc11: property.ndc_item,
+        status,
+        rxcui: parse_rxcui(&property.rxcui)?,
+        packaging: property
+            .packaging_list
+            .map(|list| list.packaging)
+            .unwrap_or_default(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct HistoricalNdcResponse {
+    #[serde(rename = "historicalNdcConcept")]
+    historical_ndc_concept: Option<HistoricalNdcConcept>,
+}
+
+#[derive(Deserialize)]
+struct HistoricalNdcConcept {
+    #[serde(rename = "historicalNdcTime", default)]
+    historical_ndc_time: Vec<HistoricalNdcTime>,
+}
+
+#[derive(Deserialize)]
+struct HistoricalNdcTime {
+    #[serde(rename = "ndcTime", default)]
+    ndc_time: Vec<NdcTime>,
+}
+
+#[derive(Deserialize)]
+struct NdcTime {
+    #[serde(default)]
+    ndc: Vec<String>,
+    #[serde(rename = "startDate", default)]
+    start_date: String,
+    #[serde(rename = "endDate")]
+    end_date: Option<String>,
+}
+
+pub(crate) fn parse_historical_ndcs(body: &str) -> Result<Vec<HistoricalNdc>, RxNormError> {
+    let response: HistoricalNdcResponse = parse_json(body)?;
+    let times = response
+        .historical_ndc_concept
+        .map(|concept| concept.historical_ndc_time)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|time| time.ndc_time);
+    let mut ndcs = Vec::new();
+    for time in times {
+        let end_date = time.end_date.filter(|date| !date.is_empty());
+        for ndc in time.ndc {
+            ndcs.push(HistoricalNdc {
+                ndc,
+                start_date: time.start_date.clone(),
+                end_date: end_date.clone(),
+            });
+        }
+    }
+    Ok(ndcs)
+}
+
+/// Converts a hyphenated 10-digit NDC to the 11-digit 5-4-2 form RxNav stores.
+///
+/// Input that is not a recognizable NDC is returned trimmed but otherwise unchanged.
+pub(crate) fn normalize_ndc(ndc: &str) -> String {
+    let ndc = ndc.trim();
+    let parts: Vec<&str> = ndc.split('-').collect();
+    let all_digits = parts
+        .iter()
+        .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+    if parts.len() != 3 || !all_digits {
+        return ndc.to_string();
+    }
+    format!("{:0>5}{:0>4}{:0>2}", parts[0], parts[1], parts[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_hyphenated_ndc() {
+        assert_eq!("00781150610", normalize_ndc("0781-1506-10"));
+        assert_eq!("12345067801", normalize_ndc("12345-678-01"));
+        assert_eq!("12345678901", normalize_ndc("12345-6789-1"));
+    }
+
+    #[test]
+    fn test_normalize_leaves_plain_ndc() {
+        assert_eq!("00781150610", normalize_ndc(" 00781150610 "));
+    }
+
+    #[test]
+    fn test_parse_historical_ndcs() {
+        let body = r#"{"historicalNdcConcept":{"historicalNdcTime":[{"status":"direct","rxcui":"213269",
+            "ndcTime":[{"ndc":["00005306443"],"startDate":"200706","endDate":"201009"},
+                       {"ndc":["00005306444","00005306445"],"startDate":"201010","endDate":""}]}]}}"#;
+        let ndcs = parse_historical_ndcs(body).unwrap();
+        assert_eq!(3, ndcs.len());
+        assert_eq!(Some(String::from("201009")), ndcs[0].end_date);
+        assert_eq!("00005306445", ndcs[2].ndc);
+        assert_eq!(None, ndcs[2].end_date);
+    }
+
+    #[test]
+    fn test_parse_ndc_properties() {
+        let body = r#"{"ndcPropertyList":{"ndcProperty":[{"ndcItem":"00071015523","ndc9":"0071-0155",
+            "ndc10":"0071-0155-23","rxcui":"617314",
+            "packagingList":{"packaging":["90 TABLET, FILM COATED in 1 BOTTLE (0071-0155-23)"]},
+            "propertyConceptList":{"propertyConcept":[{"propName":"LABELER","propValue":"Parke-Davis"},
+                {"propName":"MARKETING_STATUS","propValue":"ACTIVE"}]}}]}}"#;
+        let props = parse_ndc_properties(body).unwrap().unwrap();
+        assert_eq!("00071015523", props.ndc11);
+        assert_eq!(Some(String::from("ACTIVE")), props.status);
+        assert_eq!(617314, props.rxcui);
+        assert_eq!(1, props.packaging.len());
+        assert_eq!(None, parse_ndc_properties("{}").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ndc_gives_first_concept_properties() {
+        use crate::test_server::{response, serve};
+
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["198440","209387"]}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"properties":{"rxcui":"198440","name":"acetaminophen 500 MG Oral Tablet","synonym":"","tty":"SCD","language":"ENG","suppress":"N","umlscui":""}}"#,
+            ),
+            response("200 OK", &[], r#"{"idGroup":{}}"#),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let props = rx_client
+            .resolve_ndc("0781-1506-10")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((198440, "SCD"), (props.rxcui, props.tty.as_str()));
+        assert_eq!(None, rx_client.resolve_ndc("00000000000").await.unwrap());
+    }
+}
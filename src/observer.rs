@@ -0,0 +1,33 @@
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::RxNormError;
+
+/// Callbacks invoked around every HTTP request, for exporting metrics such
+/// as request counts, latency and retries. Register one with
+/// [`RxNormClientBuilder::observer`](crate::RxNormClientBuilder::observer).
+///
+/// Every method does nothing by default, so implementations only override
+/// the events they care about. `url` is the endpoint URL without the query.
+pub trait RequestObserver: Send + Sync {
+    /// A request, including a retry, is about to be sent.
+    fn on_request(&self, _url: &str) {}
+
+    /// RxNav answered with `status` after `latency`. Called for error
+    /// statuses too.
+    fn on_response(&self, _url: &str, _status: StatusCode, _latency: Duration) {}
+
+    /// The call failed with `error` and will not be retried.
+    fn on_error(&self, _url: &str, _error: &RxNormError) {}
+
+    /// Attempt number `attempt` failed and is retried after `delay`.
+    fn on_retry(&self, _url: &str, _attempt: u32, _delay: Duration) {}
+}
+
+impl fmt::Debug for dyn RequestObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestObserver")
+    }
+}
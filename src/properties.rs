@@ -0,0 +1,475 @@
+use serde::{Deserialize, Serialize};
+
+use crate::matcher::similarity;
+use crate::{join_list, parse_json, parse_rxcui, rxcui_path, RxNormClient, RxNormError};
+
+/// Properties of a single RxNorm concept, as returned by `getRxConceptProperties`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RxConceptProperties {
+    pub rxcui: u32,
+    pub name: String,
+    pub synonym: String,
+    pub tty: String,
+    pub language: String,
+    pub suppress: String,
+    pub umlscui: String,
+}
+
+/// The suppress flag RxNav gives suppressed concepts.
+const SUPPRESSED: &str = "Y";
+
+/// A category of properties in `getAllProperties`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PropCategory {
+    Names,
+    Codes,
+    Attributes,
+    Sources,
+}
+
+impl PropCategory {
+    /// The category name as RxNav spells it in the `prop` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PropCategory::Names => "NAMES",
+            PropCategory::Codes => "CODES",
+            PropCategory::Attributes => "ATTRIBUTES",
+            PropCategory::Sources => "SOURCES",
+        }
+    }
+}
+
+/// A single property of a concept, as returned by `getAllProperties`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropConcept {
+    pub prop_category: String,
+    pub prop_name: String,
+    pub prop_value: String,
+}
+
+/// Whether a concept with this suppress flag is left out when suppressed
+/// concepts are excluded. Shared by both clients.
+pub(crate) fn excludes(exclude_suppressed: bool, suppress: &str) -> bool {
+    exclude_suppressed && suppress == SUPPRESSED
+}
+
+/// Whether a concept in `language` is left out when only `wanted` is kept.
+/// Shared by both clients.
+pub(crate) fn outside_language(wanted: Option<&str>, language: &str) -> bool {
+    wanted.is_some_and(|wanted| !language.is_empty() && !language.eq_ignore_ascii_case(wanted))
+}
+
+impl RxNormClient {
+    /// Whether a concept with this suppress flag is left out of results,
+    /// under [`exclude_suppressed`](crate::RxNormClientBuilder::exclude_suppressed).
+    pub(crate) fn excludes(&self, suppress: &str) -> bool {
+        excludes(self.exclude_suppressed, suppress)
+    }
+
+    /// Whether a concept in `language` is left out of results, under
+    /// [`language`](crate::RxNormClientBuilder::language). Concepts whose
+    /// language RxNav does not give are kept.
+    pub(crate) fn outside_language(&self, language: &str) -> bool {
+        outside_language(self.language.as_deref(), language)
+    }
+
+    /// `ids` without the suppressed concepts, looking up the properties of
+    /// each when configured to exclude them. Ids without properties are kept.
+    pub(crate) async fn without_suppressed(
+        &self,
+        ids: Option<Vec<u32>>,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let ids = match ids {
+            Some(ids) if self.exclude_suppressed => ids,
+            ids => return Ok(ids),
+        };
+        let mut kept = Vec::new();
+        for rxcui in ids {
+            let props = self.get_properties(rxcui).await?;
+            if props.is_none_or(|props| !self.excludes(&props.suppress)) {
+                kept.push(rxcui);
+            }
+        }
+        Ok(Some(kept).filter(|kept| !kept.is_empty()))
+    }
+}
+
+impl RxNormClient {
+    /// Fetches the name, term type and other properties of a concept.
+    ///
+    /// Returns `Ok(None)` when RxNav does not know the RxCUI, or when the
+    /// concept is in another [`language`](crate::RxNormClientBuilder::language)
+    /// than the one configured. An id that
+    /// cannot be an RxCUI, such as `0`, fails with
+    /// [`RxNormError::InvalidInput`] without a request; see
+    /// [`is_plausible_rxcui`](crate::is_plausible_rxcui).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use reqwest::Client;
+    /// use rxnormalizer::RxNormClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let rx_client = RxNormClient::new(Client::new(), true);
+    ///     let props = rx_client.get_properties(1151).await.unwrap().expect("Could not find 1151");
+    ///     assert_eq!("IN", props.tty);
+    /// }
+    /// ```
+    pub async fn get_properties(
+        &self,
+        rxcui: u32,
+    ) -> Result<Option<RxConceptProperties>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "properties.json")?);
+        let body = self.get(&url, &[]).await?;
+        Ok(parse_properties(&body)?.filter(|props| !self.outside_language(&props.language)))
+    }
+
+    /// Returns the RxNorm preferred name of a concept, or `None` if RxNav does
+    /// not know the RxCUI.
+    pub async fn get_name(&self, rxcui: u32) -> Result<Option<String>, RxNormError> {
+        Ok(self.get_properties(rxcui).await?.map(|props| props.name))
+    }
+
+    /// Checks a mapping by fetching the name of `rxcui` and scoring it
+    /// against `input`, as a guard against a wildly wrong match, e.g. from
+    /// [`approximate_match`](Self::approximate_match).
+    ///
+    /// Names are scored from 0 to 1 by the character pairs they share,
+    /// ignoring case and spacing, and pass when the score is at least
+    /// `min_similarity`. An RxCUI RxNav does not know never passes.
+    pub async fn verify_mapping(
+        &self,
+        input: &str,
+        rxcui: u32,
+        min_similarity: f32,
+    ) -> Result<bool, RxNormError> {
+        let name = self.get_name(rxcui).await?;
+        Ok(name.is_some_and(|name| similarity(input, &name) >= min_similarity))
+    }
+
+    /// Fetches the properties of a concept in the given categories, such as
+    /// its source vocabulary codes. An empty slice requests every category.
+    pub async fn get_all_properties(
+        &self,
+        rxcui: u32,
+        categories: &[PropCategory],
+    ) -> Result<Vec<PropConcept>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "allProperties.json")?);
+        let prop = prop_param(categories);
+        let body = self.get(&url, &[("prop", &prop)]).await?;
+        parse_prop_concepts(&body)
+    }
+
+    /// Lists the alternate names of a concept from its `NAMES` properties,
+    /// such as synonyms and Tallman or prescribable names, without the
+    /// preferred name [`get_name`](Self::get_name) returns. Names differing
+    /// only in case appear once, in the order RxNav lists them.
+    pub async fn get_synonyms(&self, rxcui: u32) -> Result<Vec<String>, RxNormError> {
+        let props = self
+            .get_all_properties(rxcui, &[PropCategory::Names])
+            .await?;
+        Ok(synonyms(props))
+    }
+
+    /// Fetches a single named property of a concept, such as `"TTY"` or
+    /// `"AVAILABLE_STRENGTH"`. Returns `None` if the concept lacks it; a
+    /// property with several values has them joined with `", "`.
+    pub async fn get_property(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+    ) -> Result<Option<String>, RxNormError> {
+        let values = self.get_property_values(rxcui, prop_name).await?;
+        Ok(join_values(values))
+    }
+
+    /// Like [`get_property`](Self::get_property), but keeps each value of the
+    /// property separate. Empty when the concept lacks it.
+    pub async fn get_property_values(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+    ) -> Result<Vec<String>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "property.json")?);
+        let body = self.get(&url, &[("propName", prop_name)]).await?;
+        parse_property_values(&body)
+    }
+
+    /// Returns `rxcui` if the concept has property `prop_name` with one of
+    /// `prop_values`, such as `("SOURCE", &["RXNORM"])`, and `None` otherwise.
+    pub async fn filter_by_property(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+        prop_values: &[&str],
+    ) -> Result<Option<u32>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "filter.json")?);
+        let prop_values = join_list(prop_values);
+        let body = self
+            .get(
+                &url,
+                &[("propName", prop_name), ("propValues", &prop_values)],
+            )
+            .await?;
+        parse_filtered_rxcui(&body)
+    }
+}
+
+/// The prop name RxNav gives a concept's preferred name in `NAMES`.
+const PREFERRED_NAME: &str = "RxNorm Name";
+
+/// The distinct `NAMES` values other than the preferred name.
+pub(crate) fn synonyms(props: Vec<PropConcept>) -> Vec<String> {
+    let mut seen: Vec<String> = props
+        .iter()
+        .filter(|prop| prop.prop_name == PREFERRED_NAME)
+        .map(|prop| prop.prop_value.to_lowercase())
+        .collect();
+    let mut synonyms = Vec::new();
+    for prop in props {
+        let folded = prop.prop_value.to_lowercase();
+        if prop.prop_category == PropCategory::Names.as_str()
+            && !prop.prop_value.is_empty()
+            && !seen.contains(&folded)
+        {
+            seen.push(folded);
+            synonyms.push(prop.prop_value);
+        }
+    }
+    synonyms
+}
+
+#[derive(Deserialize)]
+struct PropertiesResponse {
+    properties: Option<RawProperties>,
+}
+
+#[derive(Deserialize)]
+struct RawProperties {
+    rxcui: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    synonym: String,
+    #[serde(default)]
+    tty: String,
+    #[serde(default)]
+    language: String,
+    #[serde(default)]
+    suppress: String,
+    #[serde(default)]
+    umlscui: String,
+}
+
+pub(crate) fn parse_properties(body: &str) -> Result<Option<RxConceptProperties>, RxNormError> {
+    let response: PropertiesResponse = parse_json(body)?;
+    let props = match response.properties {
+        Some(props) => props,
+        None => return Ok(None),
+    };
+    Ok(Some(RxConceptProperties {
+        rxcui: parse_rxcui(&props.rxcui)?,
+        name: props.name,
+        synonym: props.synonym,
+        tty: props.tty,
+        language: props.language,
+        suppress: props.suppress,
+        umlscui: props.umlscui,
+    }))
+}
+
+/// The `prop` parameter for `getAllProperties`; no categories means all of them.
+pub(crate) fn prop_param(categories: &[PropCategory]) -> String {
+    if categories.is_empty() {
+        String::from("all")
+    } else {
+        join_list(categories.iter().map(|c| c.as_str()))
+    }
+}
+
+#[derive(Deserialize)]
+struct PropConceptResponse {
+    #[serde(rename = "propConceptGroup")]
+    prop_concept_group: Option<PropConceptGroup>,
+}
+
+#[derive(Deserialize)]
+struct PropConceptGroup {
+    #[serde(rename = "propConcept", default)]
+    prop_concept: Vec<RawPropConcept>,
+}
+
+#[derive(Deserialize)]
+struct RawPropConcept {
+    #[serde(rename = "propCategory", default)]
+    prop_category: String,
+    #[serde(rename = "propName", default)]
+    prop_name: String,
+    #[serde(rename = "propValue", default)]
+    prop_value: String,
+}
+
+pub(crate) fn parse_prop_concepts(body: &str) -> Result<Vec<PropConcept>, RxNormError> {
+    let response: PropConceptResponse = parse_json(body)?;
+    Ok(response
+        .prop_concept_group
+        .map(|group| group.prop_concept)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| PropConcept {
+            prop_category: c.prop_category,
+            prop_name: c.prop_name,
+            prop_value: c.prop_value,
+        })
+        .collect())
+}
+
+/// The values of a `getRxProperty` response, which uses the same shape as
+/// `getAllProperties`.
+pub(crate) fn parse_property_values(body: &str) -> Result<Vec<String>, RxNormError> {
+    Ok(parse_prop_concepts(body)?
+        .into_iter()
+        .map(|c| c.prop_value)
+        .collect())
+}
+
+pub(crate) fn join_values(values: Vec<String>) -> Option<String> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(", "))
+    }
+}
+
+#[derive(Deserialize)]
+struct FilterResponse {
+    rxcui: Option<String>,
+}
+
+pub(crate) fn parse_filtered_rxcui(body: &str) -> Result<Option<u32>, RxNormError> {
+    let response: FilterResponse = parse_json(body)?;
+    match response.rxcui {
+        Some(rxcui) if !rxcui.trim().is_empty() => parse_rxcui(&rxcui).map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_properties() {
+        let body = r#"{"properties":{"rxcui":"1151","name":"ascorbic acid","synonym":"","tty":"IN","language":"ENG","suppress":"N","umlscui":""}}"#;
+        let props = parse_properties(body).unwrap().expect("properties missing");
+        assert_eq!(1151, props.rxcui);
+        assert_eq!("ascorbic acid", props.name);
+        assert_eq!("IN", props.tty);
+    }
+
+    #[test]
+    fn test_parse_empty_properties() {
+        assert_eq!(None, parse_properties("{}").unwrap());
+    }
+
+    #[test]
+    fn test_parse_prop_concepts() {
+        let body = r#"{"propConceptGroup":{"propConcept":[
+            {"propCategory":"CODES","propName":"ATC","propValue":"A11GA01"},
+            {"propCategory":"NAMES","propName":"RxNorm Name","propValue":"ascorbic acid"}]}}"#;
+        let props = parse_prop_concepts(body).unwrap();
+        assert_eq!(2, props.len());
+        assert_eq!("ATC", props[0].prop_name);
+        assert_eq!("A11GA01", props[0].prop_value);
+    }
+
+    #[test]
+    fn test_synonyms_skip_preferred_name_and_case_duplicates() {
+        let body = r#"{"propConceptGroup":{"propConcept":[
+            {"propCategory":"NAMES","propName":"RxNorm Name","propValue":"ascorbic acid"},
+            {"propCategory":"NAMES","propName":"RxNorm Synonym","propValue":"Vitamin C"},
+            {"propCategory":"NAMES","propName":"Prescribable Synonym","propValue":"vitamin C"},
+            {"propCategory":"NAMES","propName":"Tallman Synonym","propValue":"Ascorbic Acid"}]}}"#;
+        let props = parse_prop_concepts(body).unwrap();
+        assert_eq!(vec!["Vitamin C"], synonyms(props));
+    }
+
+    #[test]
+    fn test_parse_missing_prop_concepts() {
+        let body = r#"{"propConceptGroup":null}"#;
+        assert!(parse_prop_concepts(body).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_filtered_rxcui() {
+        assert_eq!(
+            Some(7052),
+            parse_filtered_rxcui(r#"{"rxcui":"7052"}"#).unwrap()
+        );
+        assert_eq!(None, parse_filtered_rxcui(r#"{"rxcui":""}"#).unwrap());
+        assert_eq!(None, parse_filtered_rxcui("{}").unwrap());
+    }
+
+    #[test]
+    fn test_parse_property_values() {
+        let body = r#"{"propConceptGroup":{"propConcept":[
+            {"propCategory":"ATTRIBUTES","propName":"AVAILABLE_STRENGTH","propValue":"500 MG"},
+            {"propCategory":"ATTRIBUTES","propName":"AVAILABLE_STRENGTH","propValue":"1000 MG"}]}}"#;
+        let values = parse_property_values(body).unwrap();
+        assert_eq!(Some(String::from("500 MG, 1000 MG")), join_values(values));
+        let empty = parse_property_values(r#"{"propConceptGroup":null}"#).unwrap();
+        assert_eq!(None, join_values(empty));
+    }
+
+    #[tokio::test]
+    async fn test_find_rxcui_drops_suppressed_matches() {
+        use crate::test_server::{response, serve};
+
+        let props = |rxcui: &str, suppress: &str| {
+            format!(
+                r#"{{"properties":{{"rxcui":"{}","name":"x","tty":"SCD","suppress":"{}"}}}}"#,
+                rxcui, suppress
+            )
+        };
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1","2"]}}"#),
+            response("200 OK", &[], &props("1", "N")),
+            response("200 OK", &[], &props("2", "Y")),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .exclude_suppressed(true)
+            .build();
+        assert_eq!(Some(vec![1]), rx_client.find_rxcui("x").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_verify_mapping_scores_the_concept_name() {
+        use crate::test_server::{response, serve};
+
+        let props = r#"{"properties":{"rxcui":"1151","name":"ascorbic acid","tty":"IN"}}"#;
+        let addr = serve(vec![
+            response("200 OK", &[], props),
+            response("200 OK", &[], props),
+            response("200 OK", &[], "{}"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        assert!(rx_client
+            .verify_mapping("Ascorbic Acid 500mg", 1151, 0.6)
+            .await
+            .unwrap());
+        assert!(!rx_client
+            .verify_mapping("warfarin", 1151, 0.6)
+            .await
+            .unwrap());
+        assert!(!rx_client.verify_mapping("x", 1151, 0.0).await.unwrap());
+    }
+}
@@ -0,0 +1,165 @@
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Spaces requests out evenly so no more than a fixed number start per second.
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub(crate) fn per_second(max_requests: u32) -> Option<Self> {
+        if max_requests == 0 {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_secs(1) / max_requests,
+            next_slot: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Waits until the caller may send its next request.
+    pub(crate) async fn acquire(&self) {
+        sleep_until(self.reserve()).await;
+    }
+
+    /// Blocks the current thread until the caller may send its next request.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn acquire_blocking(&self) {
+        std::thread::sleep(self.reserve().saturating_duration_since(Instant::now()));
+    }
+
+    /// How long a request started now would wait for its slot.
+    pub(crate) fn backlog(&self) -> Duration {
+        let next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        next_slot.saturating_duration_since(Instant::now())
+    }
+
+    /// Claims the next free slot and returns when it starts.
+    fn reserve(&self) -> Instant {
+        let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = (*next_slot).max(Instant::now());
+        *next_slot = slot + self.interval;
+        slot
+    }
+}
+
+/// Caps how many requests are in flight at once.
+pub(crate) struct InFlightLimit {
+    permits: Semaphore,
+    max: usize,
+}
+
+impl InFlightLimit {
+    pub(crate) fn new(max: usize) -> Option<Self> {
+        if max == 0 {
+            return None;
+        }
+        Some(Self {
+            permits: Semaphore::new(max),
+            max,
+        })
+    }
+
+    /// Waits for a free permit; the request is in flight until it is dropped.
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.permits.acquire().await.ok()
+    }
+
+    /// The number of permits currently held.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.max - self.permits.available_permits()
+    }
+}
+
+/// How often a client has been throttled and retried, from
+/// [`throttle_stats`](crate::RxNormClient::throttle_stats), to tune rate and
+/// concurrency limits with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThrottleStats {
+    /// Responses with status `429 Too Many Requests` or
+    /// `503 Service Unavailable`, whether retried or not.
+    pub rate_limited_count: u64,
+    /// Retries of any failed request.
+    pub retry_count: u64,
+    /// The time spent sleeping before retries.
+    pub total_wait_time: Duration,
+}
+
+/// Running totals behind [`ThrottleStats`], shared by clones of a client.
+#[derive(Default)]
+pub(crate) struct ThrottleCounters {
+    rate_limited: AtomicU64,
+    retries: AtomicU64,
+    waited_micros: AtomicU64,
+}
+
+impl ThrottleCounters {
+    pub(crate) fn record_throttled(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a retry that first sleeps for `delay`.
+    pub(crate) fn record_retry(&self, delay: Duration) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(delay.as_micros()).unwrap_or(u64::MAX);
+        self.waited_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> ThrottleStats {
+        ThrottleStats {
+            rate_limited_count: self.rate_limited.load(Ordering::Relaxed),
+            retry_count: self.retries.load(Ordering::Relaxed),
+            total_wait_time: Duration::from_micros(self.waited_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_disables_limiter() {
+        assert!(RateLimiter::per_second(0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_spaces_requests() {
+        let limiter = RateLimiter::per_second(100).unwrap();
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn test_backlog_grows_with_reserved_slots() {
+        let limiter = RateLimiter::per_second(10).unwrap();
+        assert_eq!(Duration::ZERO, limiter.backlog());
+        limiter.reserve();
+        limiter.reserve();
+        assert!(limiter.backlog() > Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_limit_waits_for_a_free_permit() {
+        assert!(InFlightLimit::new(0).is_none());
+        let limit = InFlightLimit::new(2).unwrap();
+        let first = limit.acquire().await;
+        let _second = limit.acquire().await;
+        assert_eq!(2, limit.in_flight());
+        let third = tokio::time::timeout(Duration::from_millis(20), limit.acquire()).await;
+        assert!(third.is_err());
+        drop(first);
+        assert_eq!(1, limit.in_flight());
+        assert!(limit.acquire().await.is_some());
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::RelatedKey;
+use crate::{join_list, parse_json, parse_rxcui, rxcui_path, RxNormClient, RxNormError, TermType};
+
+/// The term types of concepts that can be prescribed: clinical and branded
+/// drugs, packs and drug groups.
+pub(crate) const PRESCRIBABLE_TTYS: [TermType; 6] = [
+    TermType::Scd,
+    TermType::Sbd,
+    TermType::Gpck,
+    TermType::Bpck,
+    TermType::Scdg,
+    TermType::Sbdg,
+];
+
+/// Related concepts of a single term type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConceptGroup {
+    pub tty: String,
+    pub concepts: Vec<RelatedConcept>,
+}
+
+/// A concept returned by one of the related-concept endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedConcept {
+    pub rxcui: u32,
+    pub name: String,
+    pub tty: String,
+    /// RxNav's suppress flag, `Y` for a suppressed concept; empty where
+    /// RxNav does not say.
+    #[serde(default)]
+    pub suppress: String,
+    /// The source language, e.g. `ENG`; empty where RxNav does not say.
+    #[serde(default)]
+    pub language: String,
+}
+
+/// A related concept listed once, with every group it appeared under, as
+/// returned by the `_merged` related-concept methods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergedConcept {
+    pub concept: RelatedConcept,
+    /// The term types of the groups the concept was listed in, in the order
+    /// first seen.
+    pub group_ttys: Vec<String>,
+}
+
+impl RxNormClient {
+    /// Fetches the concepts related to `rxcui` that have one of the given
+    /// term types, e.g. `&["IN", "PIN"]` or `&[TermType::In, TermType::Pin]`
+    /// for its ingredients.
+    ///
+    /// With [`with_cache`](Self::with_cache) set, results are cached per
+    /// RxCUI and set of term types, so the order of `ttys` does not matter.
+    pub async fn get_related_by_type<T: AsRef<str>>(
+        &self,
+        rxcui: u32,
+        ttys: &[T],
+    ) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let mut sorted: Vec<String> = ttys.iter().map(|tty| tty.as_ref().to_string()).collect();
+        sorted.sort();
+        sorted.dedup();
+        let key = (rxcui, Some(sorted));
+        self.cached_related(key, || async {
+            let url = self.url(&rxcui_path(rxcui, "related.json")?);
+            let tty = join_list(ttys.iter().map(AsRef::as_ref));
+            let body = self.get(&url, &[("tty", &tty)]).await?;
+            parse_related_group(&body)
+        })
+        .await
+    }
+
+    /// Like [`get_related_by_type`](Self::get_related_by_type), but as a flat
+    /// list with each RxCUI once, in the order first seen.
+    pub async fn get_related_by_type_merged<T: AsRef<str>>(
+        &self,
+        rxcui: u32,
+        ttys: &[T],
+    ) -> Result<Vec<MergedConcept>, RxNormError> {
+        Ok(merge_groups(self.get_related_by_type(rxcui, ttys).await?))
+    }
+
+    /// Fetches the prescribable forms related to `rxcui`: its clinical and
+    /// branded drugs (`SCD`, `SBD`), packs (`GPCK`, `BPCK`) and drug groups
+    /// (`SCDG`, `SBDG`), e.g. everything that can be ordered for an
+    /// ingredient. This is one related-concepts request.
+    pub async fn get_prescribable_forms(
+        &self,
+        rxcui: u32,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let groups = self.get_related_by_type(rxcui, &PRESCRIBABLE_TTYS).await?;
+        Ok(groups
+            .into_iter()
+            .flat_map(|group| group.concepts)
+            .collect())
+    }
+
+    /// Fetches the concepts connected to `rxcui` by one of the given
+    /// relationships, e.g. `&["tradename_of", "has_ingredient"]`.
+    pub async fn get_related_by_rela(
+        &self,
+        rxcui: u32,
+        relas: &[&str],
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let url = self.url(&rxcui_path(rxcui, "related.json")?);
+        let rela = join_list(relas);
+        let body = self.get(&url, &[("rela", &rela)]).await?;
+        let mut concepts = parse_related_concepts(&body)?;
+        concepts.retain(|concept| !self.drops(concept));
+        Ok(concepts)
+    }
+
+    /// Fetches every concept related to `rxcui`, grouped by term type.
+    ///
+    /// Term types without any related concepts are left out. Results are
+    /// cached when [`with_cache`](Self::with_cache) is set.
+    pub async fn get_all_related(&self, rxcui: u32) -> Result<Vec<ConceptGroup>, RxNormError> {
+        let mut groups = self
+            .cached_related((rxcui, None), || async {
+                let url = self.url(&rxcui_path(rxcui, "allrelated.json")?);
+                let body = self.get(&url, &[]).await?;
+                parse_all_related_group(&body)
+            })
+            .await?;
+        groups.retain(|group| !group.concepts.is_empty());
+        Ok(groups)
+    }
+
+    /// Like [`get_all_related`](Self::get_all_related), but as a flat list
+    /// with each RxCUI once, in the order first seen.
+    pub async fn get_all_related_merged(
+        &self,
+        rxcui: u32,
+    ) -> Result<Vec<MergedConcept>, RxNormError> {
+        Ok(merge_groups(self.get_all_related(rxcui).await?))
+    }
+
+    /// Whether `concept` is left out of related-concept results, for being
+    /// suppressed or in another language than the one configured.
+    fn drops(&self, concept: &RelatedConcept) -> bool {
+        self.excludes(&concept.suppress) || self.outside_language(&concept.language)
+    }
+
+    /// The cached groups for `key`, or the result of `fetch`, which is cached.
+    /// Suppressed and other-language concepts are dropped after the cache.
+    async fn cached_related<F, Fut>(
+        &self,
+        key: RelatedKey,
+        fetch: F,
+    ) -> Result<Vec<ConceptGroup>, RxNormError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<ConceptGroup>, RxNormError>>,
+    {
+        let cached = self.cache.as_ref().and_then(|cache| cache.related(&key));
+        let mut groups = match cached {
+            Some(groups) => groups,
+            None => {
+                let groups = fetch().await?;
+                if let Some(cache) = &self.cache {
+                    cache.insert_related(key, groups.clone());
+                }
+                groups
+            }
+        };
+        for group in &mut groups {
+            group.concepts.retain(|concept| !self.drops(concept));
+        }
+        Ok(groups)
+    }
+
+    /// Yields every concept related to `rxcui` one at a time, so callers can
+    /// process and drop each concept instead of holding all the groups.
+    ///
+    /// RxNav still sends the whole response at once; concepts are converted
+    /// lazily as the stream is polled. A failed request yields a single error.
+    pub fn stream_all_related(
+        &self,
+        rxcui: u32,
+    ) -> impl Stream<Item = Result<RelatedConcept, RxNormError>> + '_ {
+        stream::once(async move {
+            let url = self.url(&rxcui_path(rxcui, "allrelated.json")?);
+            let body = self.get(&url, &[]).await?;
+            let response: AllRelatedResponse = parse_json(&body)?;
+            Ok(response.all_related_group.concept_group)
+        })
+        .flat_map(
+            |groups: Result<Vec<RawConceptGroup>, RxNormError>| match groups {
+                Ok(groups) => stream::iter(
+                    groups
+                        .into_iter()
+                        .flat_map(|group| group.concept_properties)
+                        .map(RawConcept::into_concept),
+                )
+                .left_stream(),
+                Err(e) => stream::iter(Some(Err(e))).right_stream(),
+            },
+        )
+        .filter(move |concept| {
+            let excluded = matches!(concept, Ok(c) if self.drops(c));
+            future::ready(!excluded)
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RelatedResponse {
+    #[serde(rename = "relatedGroup", default)]
+    related_group: RelatedGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct RelatedGroup {
+    #[serde(rename = "conceptGroup", default)]
+    concept_group: Vec<RawConceptGroup>,
+}
+
+#[derive(Deserialize)]
+struct AllRelatedResponse {
+    #[serde(rename = "allRelatedGroup", default)]
+    all_related_group: RelatedGroup,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawConceptGroup {
+    #[serde(default)]
+    tty: String,
+    #[serde(rename = "conceptProperties", default)]
+    concept_properties: Vec<RawConcept>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawConcept {
+    rxcui: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tty: String,
+    #[serde(default)]
+    suppress: String,
+    #[serde(default)]
+    language: String,
+}
+
+impl RawConcept {
+    pub(crate) fn into_concept(self) -> Result<RelatedConcept, RxNormError> {
+        Ok(RelatedConcept {
+            rxcui: parse_rxcui(&self.rxcui)?,
+            name: self.name,
+            tty: self.tty,
+            suppress: self.suppress,
+            language: self.language,
+        })
+    }
+}
+
+pub(crate) fn into_concept_groups(
+    groups: Vec<RawConceptGroup>,
+) -> Result<Vec<ConceptGroup>, RxNormError> {
+    groups
+        .into_iter()
+        .map(|group| {
+            Ok(ConceptGroup {
+                tty: group.tty,
+                concepts: group
+                    .concept_properties
+                    .into_iter()
+                    .map(RawConcept::into_concept)
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect()
+}
+
+/// Flattens `groups`, listing each RxCUI once with the term types of every
+/// group it was in.
+pub(crate) fn merge_groups(groups: Vec<ConceptGroup>) -> Vec<MergedConcept> {
+    let mut merged: Vec<MergedConcept> = Vec::new();
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    for group in groups {
+        for concept in group.concepts {
+            match index.get(&concept.rxcui) {
+                Some(&at) => {
+                    let group_ttys = &mut merged[at].group_ttys;
+                    if !group_ttys.contains(&group.tty) {
+                        group_ttys.push(group.tty.clone());
+                    }
+                }
+                None => {
+                    index.insert(concept.rxcui, merged.len());
+                    merged.push(MergedConcept {
+                        concept,
+                        group_ttys: vec![group.tty.clone()],
+                    });
+                }
+            }
+        }
+    }
+    merged
+}
+
+pub(crate) fn parse_related_group(body: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+    let response: RelatedResponse = parse_json(body)?;
+    into_concept_groups(response.related_group.concept_group)
+}
+
+/// The concepts of a `related.json` response, without their grouping.
+pub(crate) fn parse_related_concepts(body: &str) -> Result<Vec<RelatedConcept>, RxNormError> {
+    Ok(parse_related_group(body)?
+        .into_iter()
+        .flat_map(|group| group.concepts)
+        .collect())
+}
+
+pub(crate) fn parse_all_related_group(body: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+    let response: AllRelatedResponse = parse_json(body)?;
+    let mut groups = into_concept_groups(response.all_related_group.concept_group)?;
+    groups.retain(|group| !group.concepts.is_empty());
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    #[test]
+    fn test_parse_related_group() {
+        let body = r#"{"relatedGroup":{"rxcui":"","termType":["IN","PIN"],"conceptGroup":[
+            {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","synonym":"","tty":"IN","language":"ENG","suppress":"N","umlscui":""}]},
+            {"tty":"PIN"}]}}"#;
+        let groups = parse_related_group(body).unwrap();
+        assert_eq!(2, groups.len());
+        assert_eq!(
+            vec![RelatedConcept {
+                rxcui: 161,
+                name: String::from("acetaminophen"),
+                tty: String::from("IN"),
+                suppress: String::from("N"),
+                language: String::from("ENG"),
+            }],
+            groups[0].concepts
+        );
+        assert!(groups[1].concepts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_groups_lists_each_rxcui_once() {
+        let body = r#"{"allRelatedGroup":{"conceptGroup":[
+            {"tty":"SCD","conceptProperties":[
+                {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"},
+                {"rxcui":"198440","name":"acetaminophen 500 MG Oral Tablet","tty":"SCD"}]},
+            {"tty":"SCDG","conceptProperties":[
+                {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"}]},
+            {"tty":"SCD","conceptProperties":[
+                {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"}]}]}}"#;
+        let merged = merge_groups(parse_all_related_group(body).unwrap());
+        let summary: Vec<(u32, Vec<&str>)> = merged
+            .iter()
+            .map(|m| {
+                let ttys = m.group_ttys.iter().map(String::as_str).collect();
+                (m.concept.rxcui, ttys)
+            })
+            .collect();
+        assert_eq!(
+            vec![(313782, vec!["SCD", "SCDG"]), (198440, vec!["SCD"])],
+            summary
+        );
+    }
+
+    #[test]
+    fn test_parse_related_concepts_flattens_groups() {
+        let body = r#"{"relatedGroup":{"rxcui":"","conceptGroup":[
+            {"tty":"BN","conceptProperties":[{"rxcui":"202433","name":"Tylenol","tty":"BN"}]},
+            {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#;
+        let ids: Vec<u32> = parse_related_concepts(body)
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![202433, 161], ids);
+    }
+
+    #[test]
+    fn test_parse_all_related_drops_empty_groups() {
+        let body = r#"{"allRelatedGroup":{"rxcui":"","conceptGroup":[
+            {"tty":"BN"},
+            {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#;
+        let groups = parse_all_related_group(body).unwrap();
+        assert_eq!(1, groups.len());
+        assert_eq!("IN", groups[0].tty);
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_related_flattens_groups() {
+        let body = r#"{"allRelatedGroup":{"conceptGroup":[
+            {"tty":"BN","conceptProperties":[{"rxcui":"202433","name":"Tylenol","tty":"BN"}]},
+            {"tty":"DF"},
+            {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#;
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let ids: Vec<u32> = rx_client
+            .stream_all_related(161)
+            .map(|concept| concept.unwrap().rxcui)
+            .collect()
+            .await;
+        assert_eq!(vec![202433, 161], ids);
+    }
+
+    #[tokio::test]
+    async fn test_related_by_type_is_cached_whatever_the_tty_order() {
+        let body = r#"{"relatedGroup":{"conceptGroup":[
+            {"tty":"IN","conceptProperties":[{"rxcui":"161","name":"acetaminophen","tty":"IN"}]}]}}"#;
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .build()
+            .with_cache(4);
+        let first = rx_client
+            .get_related_by_type(209387, &["IN", "PIN"])
+            .await
+            .unwrap();
+        let second = rx_client
+            .get_related_by_type(209387, &["PIN", "IN"])
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(1, rx_client.cache_stats().hits);
+    }
+
+    #[tokio::test]
+    async fn test_suppressed_related_concepts_are_dropped() {
+        let body = r#"{"relatedGroup":{"conceptGroup":[{"tty":"SCD","conceptProperties":[
+            {"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD","suppress":"N"},
+            {"rxcui":"1","name":"obsolete","tty":"SCD","suppress":"Y"}]}]}}"#;
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .exclude_suppressed(true)
+            .build();
+        let ids: Vec<u32> = rx_client
+            .get_related_by_rela(209387, &["tradename_of"])
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![313782], ids);
+    }
+
+    #[tokio::test]
+    async fn test_other_language_related_concepts_are_dropped() {
+        let body = r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[
+            {"rxcui":"161","name":"acetaminophen","tty":"IN","language":"ENG"},
+            {"rxcui":"9001","name":"paracetamol","tty":"IN","language":"SPA"},
+            {"rxcui":"9002","name":"sin idioma","tty":"IN"}]}]}}"#;
+        let addr = serve(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .language("spa")
+            .build();
+        let ids: Vec<u32> = rx_client
+            .get_related_by_rela(209387, &["has_ingredient"])
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![9001, 9002], ids);
+    }
+
+    #[tokio::test]
+    async fn test_prescribable_forms_request_their_term_types() {
+        use crate::test_server::serve_recording;
+
+        let body = r#"{"relatedGroup":{"conceptGroup":[
+            {"tty":"SCD","conceptProperties":[{"rxcui":"313782","name":"acetaminophen 325 MG Oral Tablet","tty":"SCD"}]},
+            {"tty":"SBD","conceptProperties":[{"rxcui":"209387","name":"acetaminophen 325 MG Oral Tablet [Tylenol]","tty":"SBD"}]},
+            {"tty":"GPCK"}]}}"#;
+        let (addr, requests) = serve_recording(vec![response("200 OK", &[], body)]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build();
+        let ids: Vec<u32> = rx_client
+            .get_prescribable_forms(161)
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![313782, 209387], ids);
+        assert_eq!(
+            "GET /rxcui/161/related.json?tty=SCD+SBD+GPCK+BPCK+SCDG+SBDG HTTP/1.1",
+            requests.recv().unwrap()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+use crate::{RxNormClient, RxNormError};
+
+/// The RxNorm relationships followed by
+/// [`relationship_between`](RxNormClient::relationship_between).
+const RELATIONSHIPS: &[&str] = &[
+    "tradename_of",
+    "has_tradename",
+    "consists_of",
+    "constitutes",
+    "has_ingredient",
+    "ingredient_of",
+    "has_precise_ingredient",
+    "precise_ingredient_of",
+    "has_form",
+    "form_of",
+    "isa",
+    "inverse_isa",
+    "contains",
+    "contained_in",
+    "has_quantified_form",
+    "quantified_form_of",
+];
+
+/// The most relationships a path found by
+/// [`relationship_between`](RxNormClient::relationship_between) may have.
+const MAX_PATH_LENGTH: usize = 2;
+
+/// Related-concept requests kept in flight while expanding one concept.
+const RELATIONSHIP_CONCURRENCY: usize = 4;
+
+impl RxNormClient {
+    /// Finds how `to` is reached from `from`, as the relationship labels along
+    /// the shortest path, e.g. `["consists_of", "has_ingredient"]` from a
+    /// branded drug to its ingredient.
+    ///
+    /// Paths of up to two relationships between drug concepts are searched.
+    /// RxNav does not label the concepts `allrelated` returns, so each concept
+    /// on the way costs one request per relationship. An empty vec means no
+    /// path was found, or that `from` and `to` are the same concept.
+    pub async fn relationship_between(
+        &self,
+        from: u32,
+        to: u32,
+    ) -> Result<Vec<String>, RxNormError> {
+        shortest_path(from, to, |rxcui| self.relationships_of(rxcui)).await
+    }
+
+    /// The relationships of `rxcui` as `(from, rela, to)` edges, such as
+    /// `(209387, "tradename_of", 198440)`, ready to load into a graph
+    /// library. Each edge is given once.
+    ///
+    /// The same relationships as
+    /// [`relationship_between`](Self::relationship_between) are followed, at
+    /// one request per relationship.
+    pub async fn get_relationship_edges(
+        &self,
+        rxcui: u32,
+    ) -> Result<Vec<(u32, String, u32)>, RxNormError> {
+        Ok(into_edges(rxcui, self.relationships_of(rxcui).await?))
+    }
+
+    /// The concepts directly related to `rxcui`, each with its relationship.
+    async fn relationships_of(&self, rxcui: u32) -> Result<Vec<(&'static str, u32)>, RxNormError> {
+        let related: Vec<Vec<(&'static str, u32)>> = stream::iter(RELATIONSHIPS)
+            .map(|&rela| async move {
+                let concepts = self.get_related_by_rela(rxcui, &[rela]).await?;
+                Ok::<_, RxNormError>(concepts.into_iter().map(|c| (rela, c.rxcui)).collect())
+            })
+            .buffered(RELATIONSHIP_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(related.into_iter().flatten().collect())
+    }
+}
+
+/// The `(from, rela, to)` edges from `from` to its related concepts, without
+/// repeats.
+fn into_edges(from: u32, related: Vec<(&'static str, u32)>) -> Vec<(u32, String, u32)> {
+    let mut edges: Vec<(u32, String, u32)> = Vec::new();
+    for (rela, to) in related {
+        let edge = (from, rela.to_string(), to);
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    }
+    edges
+}
+
+/// Breadth-first search from `from` to `to` over the edges `neighbours`
+/// returns, up to [`MAX_PATH_LENGTH`] edges deep.
+async fn shortest_path<F, Fut>(
+    from: u32,
+    to: u32,
+    mut neighbours: F,
+) -> Result<Vec<String>, RxNormError>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<Vec<(&'static str, u32)>, RxNormError>>,
+{
+    if from == to {
+        return Ok(Vec::new());
+    }
+    let mut paths: HashMap<u32, Vec<&'static str>> = HashMap::new();
+    paths.insert(from, Vec::new());
+    let mut frontier = vec![from];
+    for _ in 0..MAX_PATH_LENGTH {
+        let mut next = Vec::new();
+        for rxcui in frontier {
+            for (rela, neighbour) in neighbours(rxcui).await? {
+                if paths.contains_key(&neighbour) {
+                    continue;
+                }
+                let mut path = paths[&rxcui].clone();
+                path.push(rela);
+                if neighbour == to {
+                    return Ok(path.into_iter().map(String::from).collect());
+                }
+                paths.insert(neighbour, path);
+                next.push(neighbour);
+            }
+        }
+        frontier = next;
+    }
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(rxcui: u32) -> impl Future<Output = Result<Vec<(&'static str, u32)>, RxNormError>> {
+        let edges = match rxcui {
+            // Tylenol 500 MG Oral Tablet
+            209387 => vec![("tradename_of", 198440), ("consists_of", 315266)],
+            // acetaminophen 500 MG
+            315266 => vec![("constitutes", 209387), ("has_ingredient", 161)],
+            198440 => vec![("has_ingredient", 161)],
+            _ => Vec::new(),
+        };
+        async move { Ok(edges) }
+    }
+
+    #[tokio::test]
+    async fn test_shortest_path_labels_each_step() {
+        let path = shortest_path(209387, 161, graph).await.unwrap();
+        assert_eq!(vec!["tradename_of", "has_ingredient"], path);
+        let direct = shortest_path(209387, 315266, graph).await.unwrap();
+        assert_eq!(vec!["consists_of"], direct);
+    }
+
+    #[tokio::test]
+    async fn test_edges_start_at_the_concept() {
+        let mut related = graph(315266).await.unwrap();
+        related.push(("has_ingredient", 161));
+        assert_eq!(
+            vec![
+                (315266, String::from("constitutes"), 209387),
+                (315266, String::from("has_ingredient"), 161),
+            ],
+            into_edges(315266, related)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_concept_gives_empty_path() {
+        assert!(shortest_path(209387, 1, graph).await.unwrap().is_empty());
+        assert!(shortest_path(161, 161, graph).await.unwrap().is_empty());
+    }
+}
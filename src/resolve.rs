@@ -0,0 +1,584 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parse_drug_components, ApproximateCandidate, DrugComponents, RxNormClient, RxNormError,
+    SearchMode, TermType, FALLBACK_CANDIDATES,
+};
+
+/// How a drug string was matched by [`RxNormClient::resolve_rxcui`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    /// The name matched exactly.
+    Exact(Vec<u32>),
+    /// The name matched after RxNav's string normalization.
+    Normalized(Vec<u32>),
+    /// Only an approximate match was found, with its match score.
+    Approximate { rxcui: u32, score: i32 },
+}
+
+impl Resolution {
+    /// The matched RxCUIs, whatever the match quality.
+    pub fn rxcuis(&self) -> Vec<u32> {
+        match self {
+            Resolution::Exact(ids) | Resolution::Normalized(ids) => ids.clone(),
+            Resolution::Approximate { rxcui, .. } => vec![*rxcui],
+        }
+    }
+}
+
+/// The result of [`RxNormClient::resolve_with_suggestions`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResolveOutcome {
+    /// The name search matched these RxCUIs.
+    Found(Vec<u32>),
+    /// Nothing matched, but RxNav suggests these spellings.
+    DidYouMean(Vec<String>),
+    /// Nothing matched and RxNav has no suggestions.
+    NotFound,
+}
+
+/// Why a drug string did or did not resolve, as reported by
+/// [`RxNormClient::diagnose`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Diagnosis {
+    /// The name matched these RxCUIs exactly.
+    ResolvedExact(Vec<u32>),
+    /// The name only matched these RxCUIs after RxNav's string normalization.
+    ResolvedNormalized(Vec<u32>),
+    /// Nothing matched, but RxNav suggests these spellings.
+    SpellingSuggestions(Vec<String>),
+    /// Nothing matched or was suggested; these are the closest concepts.
+    ApproximateOnly(Vec<ApproximateCandidate>),
+    /// RxNav has nothing resembling the name.
+    Unknown,
+}
+
+/// The RxCUIs a name matches exactly and the further ones RxNav's
+/// normalized search adds, as returned by [`RxNormClient::find_rxcui_grouped`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchGroups {
+    pub exact: Vec<u32>,
+    /// Normalized matches that are not also exact matches.
+    pub normalized: Vec<u32>,
+}
+
+/// A drug string together with the RxCUIs it matched and the preferred name
+/// of the first one, as returned by [`RxNormClient::resolve`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedDrug {
+    pub input: String,
+    pub rxcuis: Vec<u32>,
+    /// `None` if RxNav has no properties for the first RxCUI.
+    pub name: Option<String>,
+    /// `name` split into ingredient, strength and dose form, when it has one.
+    pub components: Option<DrugComponents>,
+}
+
+impl RxNormClient {
+    /// Finds the RxCUIs for `drug` and the preferred name of the first one,
+    /// e.g. for logging `mapped 'tylenol' -> acetaminophen [161]`.
+    ///
+    /// This makes one name search and, when something matched, one
+    /// properties request.
+    pub async fn resolve(&self, drug: &str) -> Result<Option<ResolvedDrug>, RxNormError> {
+        let rxcuis = match self.find_rxcui(drug).await? {
+            Some(rxcuis) if !rxcuis.is_empty() => rxcuis,
+            _ => return Ok(None),
+        };
+        let name = self.get_name(rxcuis[0]).await?;
+        Ok(Some(ResolvedDrug {
+            input: drug.to_string(),
+            rxcuis,
+            components: name.as_deref().map(parse_drug_components),
+            name,
+        }))
+    }
+
+    /// Like [`find_rxcui`](Self::find_rxcui), but reports how the name was
+    /// matched.
+    ///
+    /// With [`SearchMode::ExactThenNormalized`] this sends the exact and the
+    /// normalized search separately so the two can be told apart. Under
+    /// [`fallback_to_exact_on_normalized_failure`](crate::RxNormClientBuilder::fallback_to_exact_on_normalized_failure)
+    /// a failed normalized search counts as finding nothing, and with
+    /// [`SearchMode::Normalized`] is followed by an exact one. The
+    /// approximate step only runs when
+    /// [`approximate_fallback`](crate::RxNormClientBuilder::approximate_fallback)
+    /// is enabled.
+    pub async fn resolve_rxcui(&self, drug: &str) -> Result<Option<Resolution>, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        if self.search_mode != SearchMode::Normalized {
+            if let Some(ids) = self.lookup_kept(drug, SearchMode::Exact).await? {
+                return Ok(Some(Resolution::Exact(ids)));
+            }
+        }
+        if self.search_mode != SearchMode::Exact {
+            let normalized = self.lookup_name(drug, SearchMode::Normalized, None).await;
+            if !self.falls_back_to_exact(&normalized) {
+                if let Some(ids) = self.without_suppressed(normalized?).await? {
+                    return Ok(Some(Resolution::Normalized(ids)));
+                }
+            } else if self.search_mode == SearchMode::Normalized {
+                if let Some(ids) = self.lookup_kept(drug, SearchMode::Exact).await? {
+                    return Ok(Some(Resolution::Exact(ids)));
+                }
+            }
+        }
+        if !self.approximate_fallback {
+            return Ok(None);
+        }
+        let (rxcui, score) = match self.approximate_rxcui(drug, None).await? {
+            Some(approximate) => approximate,
+            None => return Ok(None),
+        };
+        let kept = self.without_suppressed(Some(vec![rxcui])).await?;
+        Ok(kept.map(|_| Resolution::Approximate { rxcui, score }))
+    }
+
+    /// Finds the RxCUIs for `drug` with the exact and the normalized matches
+    /// kept apart, so higher-confidence exact matches can be preferred.
+    ///
+    /// RxNav's combined search does not say which pass matched an id, so this
+    /// sends the exact and the normalized search separately, whatever the
+    /// configured search mode.
+    pub async fn find_rxcui_grouped(&self, drug: &str) -> Result<MatchGroups, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        let exact = self
+            .lookup_kept(drug, SearchMode::Exact)
+            .await?
+            .unwrap_or_default();
+        let normalized = self
+            .lookup_kept(drug, SearchMode::Normalized)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|rxcui| !exact.contains(rxcui))
+            .collect();
+        Ok(MatchGroups { exact, normalized })
+    }
+
+    /// Explains what `drug` resolves to, or why it does not, for users
+    /// puzzled by a `None` from [`find_rxcui`](Self::find_rxcui).
+    ///
+    /// Tries an exact search, a normalized search, spelling suggestions and
+    /// an approximate match in that order, whatever the configured search
+    /// mode, and stops at the first that finds anything. This can cost four
+    /// requests.
+    pub async fn diagnose(&self, drug: &str) -> Result<Diagnosis, RxNormError> {
+        let drug = &self.prepare_term(drug)?;
+        if let Some(ids) = self.lookup_kept(drug, SearchMode::Exact).await? {
+            return Ok(Diagnosis::ResolvedExact(ids));
+        }
+        if let Some(ids) = self.lookup_kept(drug, SearchMode::Normalized).await? {
+            return Ok(Diagnosis::ResolvedNormalized(ids));
+        }
+        let suggestions = self.spelling_suggestions(drug).await?;
+        if !suggestions.is_empty() {
+            return Ok(Diagnosis::SpellingSuggestions(suggestions));
+        }
+        let candidates = self.approximate_match(drug, FALLBACK_CANDIDATES).await?;
+        if !candidates.is_empty() {
+            return Ok(Diagnosis::ApproximateOnly(candidates));
+        }
+        Ok(Diagnosis::Unknown)
+    }
+
+    /// Searches for `drug` and, only when nothing matches, asks RxNav for
+    /// spelling suggestions to offer instead.
+    pub async fn resolve_with_suggestions(
+        &self,
+        drug: &str,
+    ) -> Result<ResolveOutcome, RxNormError> {
+        if let Some(ids) = self.find_rxcui(drug).await? {
+            return Ok(ResolveOutcome::Found(ids));
+        }
+        let suggestions = self.spelling_suggestions(drug).await?;
+        if suggestions.is_empty() {
+            Ok(ResolveOutcome::NotFound)
+        } else {
+            Ok(ResolveOutcome::DidYouMean(suggestions))
+        }
+    }
+
+    /// Finds the ingredient (`IN`) concepts of whatever `drug` matches, e.g.
+    /// both ingredients of a combination product. Each RxCUI appears once,
+    /// in the order first found.
+    ///
+    /// This makes one name search plus one related-concepts request per
+    /// matched RxCUI.
+    pub async fn find_ingredient_rxcuis(&self, drug: &str) -> Result<Vec<u32>, RxNormError> {
+        let ids = self.find_rxcui(drug).await?.unwrap_or_default();
+        let mut ingredients = Vec::new();
+        for rxcui in ids {
+            let groups = self.get_related_by_type(rxcui, &[TermType::In]).await?;
+            for concept in groups.into_iter().flat_map(|group| group.concepts) {
+                if !ingredients.contains(&concept.rxcui) {
+                    ingredients.push(concept.rxcui);
+                }
+            }
+        }
+        Ok(ingredients)
+    }
+
+    /// Whether drug strings `a` and `b` share an ingredient, e.g. `Tylenol`
+    /// and `acetaminophen 500 MG`, as found by
+    /// [`find_ingredient_rxcuis`](Self::find_ingredient_rxcuis).
+    ///
+    /// A string that matches nothing shares nothing, so gives `false`. When
+    /// `a` has no ingredients `b` is not looked up.
+    pub async fn same_ingredient(&self, a: &str, b: &str) -> Result<bool, RxNormError> {
+        let ingredients = self.find_ingredient_rxcuis(a).await?;
+        if ingredients.is_empty() {
+            return Ok(false);
+        }
+        let other = self.find_ingredient_rxcuis(b).await?;
+        Ok(other.iter().any(|rxcui| ingredients.contains(rxcui)))
+    }
+
+    /// Finds the RxCUIs for a drug string, keeping only concepts whose term
+    /// type is in `allowed_ttys`.
+    ///
+    /// This makes one name search plus one properties request per matched
+    /// RxCUI, so a broad term can cost many requests.
+    pub async fn find_rxcui_filtered(
+        &self,
+        drug: &str,
+        allowed_ttys: &[TermType],
+    ) -> Result<Vec<u32>, RxNormError> {
+        let ids = self.find_candidates(drug).await?;
+        let mut kept = Vec::new();
+        for rxcui in ids {
+            if let Some(props) = self.get_properties(rxcui).await? {
+                if allowed_ttys.contains(&TermType::from(props.tty.as_str()))
+                    && !self.excludes(&props.suppress)
+                {
+                    kept.push(rxcui);
+                }
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Finds a single RxCUI for `drug`: the first match whose term type is
+    /// `preferred_tty`, or the first match at all when it is `None`.
+    ///
+    /// With a preferred term type this makes one properties request per
+    /// match until one has that type, and gives `None` if none does.
+    pub async fn find_one_rxcui(
+        &self,
+        drug: &str,
+        preferred_tty: Option<TermType>,
+    ) -> Result<Option<u32>, RxNormError> {
+        let preferred_tty = match preferred_tty {
+            Some(tty) => tty,
+            None => {
+                let ids = self.find_rxcui(drug).await?.unwrap_or_default();
+                return Ok(ids.first().copied());
+            }
+        };
+        for rxcui in self.find_candidates(drug).await? {
+            if let Some(props) = self.get_properties(rxcui).await? {
+                if TermType::from(props.tty.as_str()) == preferred_tty
+                    && !self.excludes(&props.suppress)
+                {
+                    return Ok(Some(rxcui));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// A name search for an already normalized `drug` in `search_mode`,
+    /// without the concepts
+    /// [`exclude_suppressed`](crate::RxNormClientBuilder::exclude_suppressed)
+    /// leaves out.
+    async fn lookup_kept(
+        &self,
+        drug: &str,
+        search_mode: SearchMode,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        let ids = self.lookup_name(drug, search_mode, None).await?;
+        self.without_suppressed(ids).await
+    }
+
+    /// The matches for `drug`, suppressed ones included, for callers that
+    /// fetch each match's properties anyway and check the suppress flag
+    /// there instead of paying for a second lookup.
+    async fn find_candidates(&self, drug: &str) -> Result<Vec<u32>, RxNormError> {
+        if self.fixtures.is_some() || !self.exclude_suppressed {
+            return Ok(self.find_rxcui(drug).await?.unwrap_or_default());
+        }
+        let drug = &self.prepare_term(drug)?;
+        let ids = self.find_name_with_suppressed(drug, None).await?;
+        Ok(ids.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    #[tokio::test]
+    async fn test_resolve_names_first_match() {
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["161"]}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"properties":{"rxcui":"161","name":"acetaminophen","tty":"IN"}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let resolved = rx_client.resolve("tylenol").await.unwrap().unwrap();
+        assert_eq!(vec![161], resolved.rxcuis);
+        assert_eq!(Some(String::from("acetaminophen")), resolved.name);
+        assert_eq!(Some(DrugComponents::default()), resolved.components);
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_reports_spelling_suggestions() {
+        let no_match = r#"{"idGroup":{"name":"tylenool"}}"#;
+        let addr = serve(vec![
+            response("200 OK", &[], no_match),
+            response("200 OK", &[], no_match),
+            response(
+                "200 OK",
+                &[],
+                r#"{"suggestionGroup":{"suggestionList":{"suggestion":["tylenol"]}}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .build();
+        assert_eq!(
+            Diagnosis::SpellingSuggestions(vec![String::from("tylenol")]),
+            rx_client.diagnose("tylenool").await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingredients_of_combination_product() {
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["209459"]}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"relatedGroup":{"conceptGroup":[{"tty":"IN","conceptProperties":[
+                    {"rxcui":"161","name":"acetaminophen","tty":"IN"},
+                    {"rxcui":"2670","name":"codeine","tty":"IN"}]}]}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let ingredients = rx_client
+            .find_ingredient_rxcuis("tylenol with codeine")
+            .await;
+        assert_eq!(vec![161, 2670], ingredients.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_same_ingredient_compares_ingredient_sets() {
+        let ingredient = |rxcui: &str| {
+            response(
+                "200 OK",
+                &[],
+                &format!(
+                    r#"{{"relatedGroup":{{"conceptGroup":[{{"tty":"IN","conceptProperties":[
+                        {{"rxcui":"{}","name":"","tty":"IN"}}]}}]}}}}"#,
+                    rxcui
+                ),
+            )
+        };
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["202433"]}}"#),
+            ingredient("161"),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["198440"]}}"#),
+            ingredient("161"),
+            response("200 OK", &[], r#"{"idGroup":{}}"#),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["202433"]}}"#),
+            ingredient("161"),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1191"]}}"#),
+            ingredient("1191"),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .max_retries(0)
+            .build();
+        assert!(rx_client
+            .same_ingredient("Tylenol", "acetaminophen 500 MG")
+            .await
+            .unwrap());
+        assert!(!rx_client
+            .same_ingredient("unobtainium", "Tylenol")
+            .await
+            .unwrap());
+        assert!(!rx_client
+            .same_ingredient("Tylenol", "aspirin")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_find_one_rxcui_picks_preferred_tty() {
+        let addr = serve(vec![
+            response(
+                "200 OK",
+                &[],
+                r#"{"idGroup":{"rxnormId":["202433","209387"]}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"properties":{"rxcui":"202433","name":"Tylenol","tty":"BN"}}"#,
+            ),
+            response(
+                "200 OK",
+                &[],
+                r#"{"properties":{"rxcui":"209387","name":"Tylenol 325 MG Oral Tablet","tty":"SBD"}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let rxcui = rx_client
+            .find_one_rxcui("tylenol", Some(TermType::Sbd))
+            .await;
+        assert_eq!(Some(209387), rxcui.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_grouped_matches_keep_passes_apart() {
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"idGroup":{"rxnormId":["1151","1088438"]}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let groups = rx_client.find_rxcui_grouped("vitamin c").await.unwrap();
+        assert_eq!(vec![1151], groups.exact);
+        assert_eq!(vec![1088438], groups.normalized);
+    }
+
+    #[tokio::test]
+    async fn test_suggestions_when_nothing_matches() {
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"name":"ambienn"}}"#),
+            response(
+                "200 OK",
+                &[],
+                r#"{"suggestionGroup":{"suggestionList":{"suggestion":["ambien"]}}}"#,
+            ),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .build();
+        let outcome = rx_client.resolve_with_suggestions("ambienn").await.unwrap();
+        assert_eq!(
+            ResolveOutcome::DidYouMean(vec![String::from("ambien")]),
+            outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_falls_back_to_approximate_match() {
+        let no_match = response("200 OK", &[], r#"{"idGroup":{"name":"vitc"}}"#);
+        let candidates = response(
+            "200 OK",
+            &[],
+            r#"{"approximateGroup":{"candidate":[{"rxcui":"1151","rxaui":"8317050","score":"8.7","rank":"1"}]}}"#,
+        );
+        let addr = serve(vec![no_match.clone(), no_match, candidates]).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}/REST", addr))
+            .approximate_fallback(true)
+            .build();
+        let resolution = rx_client.resolve_rxcui("vitc").await.unwrap();
+        assert_eq!(
+            Some(Resolution::Approximate {
+                rxcui: 1151,
+                score: 9
+            }),
+            resolution
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_rxcui_leaves_out_suppressed_matches() {
+        let props = |rxcui: &str, suppress: &str| {
+            format!(
+                r#"{{"properties":{{"rxcui":"{}","name":"x","tty":"SCD","suppress":"{}"}}}}"#,
+                rxcui, suppress
+            )
+        };
+        let addr = serve(vec![
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1","2"]}}"#),
+            response("200 OK", &[], &props("1", "Y")),
+            response("200 OK", &[], &props("2", "N")),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .search_mode(SearchMode::Exact)
+            .exclude_suppressed(true)
+            .build();
+        assert_eq!(
+            Some(Resolution::Exact(vec![2])),
+            rx_client.resolve_rxcui("x").await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_normalized_search_falls_back_to_exact() {
+        use crate::test_server::serve_recording;
+
+        let (addr, requests) = serve_recording(vec![
+            response("500 Internal Server Error", &[], "{}"),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+            response("200 OK", &[], r#"{"idGroup":{}}"#),
+            response("200 OK", &[], r#"{"idGroup":{"rxnormId":["1151"]}}"#),
+        ])
+        .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .fallback_to_exact_on_normalized_failure(true)
+            .build();
+        assert_eq!(
+            Some(vec![1151]),
+            rx_client.find_rxcui("vitamin c").await.unwrap()
+        );
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .search_mode(SearchMode::Normalized)
+            .max_retries(0)
+            .fallback_to_exact_on_normalized_failure(true)
+            .build();
+        assert_eq!(
+            Some(Resolution::Exact(vec![1151])),
+            rx_client.resolve_rxcui("vitamin c").await.unwrap()
+        );
+        let searches: Vec<String> = requests
+            .try_iter()
+            .map(|line| line.split("search=").nth(1).unwrap_or_default()[..1].to_string())
+            .collect();
+        assert_eq!(vec!["2", "0", "1", "0"], searches);
+    }
+}
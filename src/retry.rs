@@ -0,0 +1,368 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::{DEFAULT_MAX_RETRIES, DEFAULT_RETRY_BACKOFF};
+
+/// How the wait before each retry is computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay before every retry.
+    Fixed(Duration),
+    /// `initial` before the first retry, doubling each time up to `max`.
+    Exponential { initial: Duration, max: Duration },
+    /// Like [`Backoff::Exponential`], but each delay is a random duration up
+    /// to the exponential one, so clients throttled together do not all
+    /// retry at the same moment.
+    ExponentialWithJitter { initial: Duration, max: Duration },
+}
+
+/// When and how often failed requests are retried, set with
+/// [`RxNormClientBuilder::retry_policy`](crate::RxNormClientBuilder::retry_policy).
+///
+/// A `Retry-After` header on a throttled response takes precedence over the
+/// computed delay. Throttled (429/503) responses are always retried; failed
+/// sends only when [`should_retry`](RetryPolicy::should_retry) allows it.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) backoff: Backoff,
+    pub(crate) max_elapsed: Option<Duration>,
+    pub(crate) should_retry: fn(&reqwest::Error) -> bool,
+    pub(crate) retry_timeouts: bool,
+}
+
+impl PartialEq for RetryPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_retries == other.max_retries
+            && self.backoff == other.backoff
+            && self.max_elapsed == other.max_elapsed
+            && std::ptr::fn_addr_eq(self.should_retry, other.should_retry)
+            && self.retry_timeouts == other.retry_timeouts
+    }
+}
+
+impl Eq for RetryPolicy {}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::fixed(DEFAULT_MAX_RETRIES, DEFAULT_RETRY_BACKOFF)
+    }
+}
+
+impl RetryPolicy {
+    /// Retries up to `max_retries` times, waiting as `backoff` says.
+    pub fn new(max_retries: u32, backoff: Backoff) -> Self {
+        Self {
+            max_retries,
+            backoff,
+            max_elapsed: None,
+            should_retry,
+            retry_timeouts: true,
+        }
+    }
+
+    /// Waits `delay` before every retry.
+    pub fn fixed(max_retries: u32, delay: Duration) -> Self {
+        Self::new(max_retries, Backoff::Fixed(delay))
+    }
+
+    /// Doubles the delay after every retry, starting at `initial` and never
+    /// exceeding `max`.
+    pub fn exponential(max_retries: u32, initial: Duration, max: Duration) -> Self {
+        Self::new(max_retries, Backoff::Exponential { initial, max })
+    }
+
+    /// Exponential backoff where each delay is randomized between zero and
+    /// the exponential delay.
+    pub fn exponential_with_jitter(max_retries: u32, initial: Duration, max: Duration) -> Self {
+        Self::new(max_retries, Backoff::ExponentialWithJitter { initial, max })
+    }
+
+    /// Stops retrying once another wait would take the request past
+    /// `max_elapsed` since its first attempt.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// Decides which failed sends are worth retrying, replacing the default
+    /// [`should_retry`](crate::should_retry) classification.
+    pub fn should_retry(mut self, should_retry: fn(&reqwest::Error) -> bool) -> Self {
+        self.should_retry = should_retry;
+        self
+    }
+
+    /// Whether an attempt that ran past the client's
+    /// [`timeout`](crate::RxNormClientBuilder::timeout) is tried again. A
+    /// timed-out attempt uses up a retry and waits the backoff like any other
+    /// failure. Defaults to `true`; `false` fails with
+    /// [`RxNormError::Timeout`](crate::RxNormError::Timeout) at once.
+    pub fn retry_timeouts(mut self, retry_timeouts: bool) -> Self {
+        self.retry_timeouts = retry_timeouts;
+        self
+    }
+
+    /// Whether a request that failed with `error` may be retried.
+    pub(crate) fn retries(&self, error: &reqwest::Error) -> bool {
+        (self.retry_timeouts || !error.is_timeout()) && (self.should_retry)(error)
+    }
+
+    /// The delay before retry number `retry`, counting from one.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => exponential(initial, max, retry),
+            Backoff::ExponentialWithJitter { initial, max } => {
+                exponential(initial, max, retry).mul_f64(random_fraction())
+            }
+        }
+    }
+
+    /// How long to wait before retry number `retry`, or `None` when the
+    /// request should give up.
+    pub(crate) fn next_delay(
+        &self,
+        retry: u32,
+        elapsed: Duration,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        let delay = retry_after.unwrap_or_else(|| self.delay(retry));
+        match self.max_elapsed {
+            Some(max_elapsed) if elapsed + delay > max_elapsed => None,
+            _ => Some(delay),
+        }
+    }
+}
+
+/// Whether a failed send is likely to succeed if tried again: timeouts,
+/// connection failures and requests cut off mid-flight are; invalid URLs,
+/// redirect loops and undecodable bodies are not.
+///
+/// A connection that broke is dropped from the pool, so a retry after a
+/// reset, such as on a pooled connection a load balancer has silently
+/// closed, goes out on a fresh connection.
+///
+/// This is the default for [`RetryPolicy::should_retry`].
+pub fn should_retry(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+fn exponential(initial: Duration, max: Duration, retry: u32) -> Duration {
+    let factor = 1u32
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    initial.checked_mul(factor).unwrap_or(max).min(max)
+}
+
+/// A random number in `[0, 1)`, taken from the randomly seeded keys of a
+/// fresh `RandomState` to avoid pulling in a random number crate.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, serve_delayed};
+    use crate::{RxNormClient, RxNormError};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_invalid_url_is_not_retried() {
+        let error = reqwest::Client::new().get("not a url").build().unwrap_err();
+        assert!(!should_retry(&error));
+    }
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy =
+            RetryPolicy::exponential(10, Duration::from_millis(100), Duration::from_millis(500));
+        assert_eq!(Duration::from_millis(100), policy.delay(1));
+        assert_eq!(Duration::from_millis(400), policy.delay(3));
+        assert_eq!(Duration::from_millis(500), policy.delay(4));
+        assert_eq!(Duration::from_millis(500), policy.delay(40));
+    }
+
+    #[test]
+    fn test_jitter_stays_below_exponential_delay() {
+        let initial = Duration::from_millis(100);
+        let max = Duration::from_secs(1);
+        let policy = RetryPolicy::exponential_with_jitter(3, initial, max);
+        for retry in 1..=3 {
+            assert!(policy.delay(retry) <= exponential(initial, max, retry));
+        }
+    }
+
+    #[test]
+    fn test_next_delay_gives_up() {
+        let policy =
+            RetryPolicy::fixed(2, Duration::from_secs(1)).max_elapsed(Duration::from_secs(3));
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            policy.next_delay(1, Duration::ZERO, None)
+        );
+        assert_eq!(None, policy.next_delay(3, Duration::ZERO, None));
+        assert_eq!(
+            None,
+            policy.next_delay(2, Duration::from_millis(2500), None)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            policy.next_delay(2, Duration::from_millis(2500), Some(Duration::ZERO))
+        );
+    }
+
+    const VERSION: &str = r#"{"version":"07-Oct-2024","apiVersion":"3.1.256"}"#;
+
+    fn ok() -> (Duration, String) {
+        (Duration::ZERO, response("200 OK", &[], VERSION))
+    }
+
+    fn status(status: &str) -> (Duration, String) {
+        (Duration::ZERO, response(status, &[], ""))
+    }
+
+    fn slow() -> (Duration, String) {
+        (Duration::from_millis(500), response("200 OK", &[], VERSION))
+    }
+
+    async fn client(
+        responses: Vec<(Duration, String)>,
+        policy: RetryPolicy,
+    ) -> (RxNormClient, std::sync::mpsc::Receiver<String>) {
+        let (addr, requests) = serve_delayed(responses).await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .timeout(Duration::from_millis(100))
+            .retry_policy(policy)
+            .build();
+        (rx_client, requests)
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::fixed(max_retries, Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_success_on_first_try() {
+        let (rx_client, requests) = client(vec![ok()], policy(2)).await;
+        assert!(rx_client.get_version().await.is_ok());
+        assert_eq!(1, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_success_on_retry_after_transient_error() {
+        let (rx_client, requests) =
+            client(vec![status("503 Service Unavailable"), ok()], policy(2)).await;
+        assert!(rx_client.get_version().await.is_ok());
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_return_last_error() {
+        let (rx_client, requests) = client(
+            vec![
+                status("429 Too Many Requests"),
+                status("503 Service Unavailable"),
+            ],
+            policy(1),
+        )
+        .await;
+        assert!(matches!(
+            rx_client.get_version().await,
+            Err(RxNormError::UnexpectedStatus(
+                StatusCode::SERVICE_UNAVAILABLE
+            ))
+        ));
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_then_success() {
+        let (rx_client, requests) = client(vec![slow(), ok()], policy(1)).await;
+        assert!(rx_client.get_version().await.is_ok());
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_count_against_retries() {
+        let (rx_client, requests) = client(vec![slow(), slow()], policy(1)).await;
+        assert!(matches!(
+            rx_client.get_version().await,
+            Err(RxNormError::Timeout)
+        ));
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_not_retried_when_disabled() {
+        let (rx_client, requests) =
+            client(vec![slow(), ok()], policy(1).retry_timeouts(false)).await;
+        assert!(matches!(
+            rx_client.get_version().await,
+            Err(RxNormError::Timeout)
+        ));
+        assert_eq!(1, requests.try_iter().count());
+    }
+
+    /// Serves `VERSION` on a kept-alive connection, then reads the next
+    /// request on it and hangs up without answering, as a load balancer does
+    /// to an idle connection it has dropped. Later connections are answered
+    /// normally. Sends out the number of each connection a request came in on.
+    async fn serve_stale_after_first() -> (std::net::SocketAddr, std::sync::mpsc::Receiver<u32>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        async fn read_request(socket: &mut tokio::net::TcpStream) -> bool {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => return false,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            true
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        tokio::spawn(async move {
+            let keep_alive = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                VERSION.len(),
+                VERSION
+            );
+            for connection in 1.. {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut answered = false;
+                while read_request(&mut socket).await {
+                    let _ = sender.send(connection);
+                    if connection == 1 && answered {
+                        break;
+                    }
+                    let _ = socket.write_all(keep_alive.as_bytes()).await;
+                    answered = true;
+                }
+            }
+        });
+        (addr, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_stale_pooled_connection_is_retried_on_a_fresh_one() {
+        let (addr, connections) = serve_stale_after_first().await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .retry_policy(policy(1))
+            .build();
+        assert!(rx_client.get_version().await.is_ok());
+        assert!(rx_client.get_version().await.is_ok());
+        assert_eq!(vec![1, 1, 2], connections.try_iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::related::RawConcept;
+use crate::{check_rxcui, parse_json, RelatedConcept, RxNormClient, RxNormError};
+
+/// An ATC class a concept belongs to, from
+/// [`get_atc_classes`](RxNormClient::get_atc_classes).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtcClass {
+    /// The ATC code, e.g. `N02BE`.
+    pub class_id: String,
+    /// The class name, e.g. `Anilides`.
+    pub class_name: String,
+    /// How the concept relates to the class; usually empty for ATC.
+    pub rela: String,
+}
+
+impl RxNormClient {
+    /// Fetches the drugs in a class of the RxClass API, e.g. class `C09AA`
+    /// with `rela_source` `ATC`, or a MeSH pharmacologic action with `MESH`.
+    ///
+    /// RxClass is served under the same REST root as RxNorm, at `/rxclass`,
+    /// so this works against any base URL that serves both. An unknown class
+    /// gives an empty vec.
+    pub async fn get_class_members(
+        &self,
+        class_id: &str,
+        rela_source: &str,
+    ) -> Result<Vec<RelatedConcept>, RxNormError> {
+        let url = self.url("/rxclass/classMembers.json");
+        let query = [("classId", class_id), ("relaSource", rela_source)];
+        let body = self.get(&url, &query).await?;
+        parse_class_members(&body)
+    }
+
+    /// Fetches the ATC classes of a concept from RxClass, e.g. `N02BE`
+    /// (anilides) for acetaminophen. RxClass lists a class once per
+    /// ingredient that puts the concept in it; each class is given once
+    /// here. A concept without ATC classes gives an empty vec.
+    pub async fn get_atc_classes(&self, rxcui: u32) -> Result<Vec<AtcClass>, RxNormError> {
+        check_rxcui(rxcui)?;
+        let url = self.url("/rxclass/class/byRxcui.json");
+        let rxcui = rxcui.to_string();
+        let body = self
+            .get(&url, &[("rxcui", &rxcui), ("relaSource", "ATC")])
+            .await?;
+        parse_atc_classes(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct ClassMembersResponse {
+    #[serde(rename = "drugMemberGroup", default)]
+    drug_member_group: DrugMemberGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct DrugMemberGroup {
+    #[serde(rename = "drugMember", default)]
+    drug_member: Vec<DrugMember>,
+}
+
+#[derive(Deserialize)]
+struct DrugMember {
+    #[serde(rename = "minConcept")]
+    min_concept: RawConcept,
+}
+
+#[derive(Deserialize)]
+struct ClassesByRxcuiResponse {
+    #[serde(rename = "rxclassDrugInfoList", default)]
+    drug_info_list: DrugInfoList,
+}
+
+#[derive(Deserialize, Default)]
+struct DrugInfoList {
+    #[serde(rename = "rxclassDrugInfo", default)]
+    drug_info: Vec<DrugInfo>,
+}
+
+#[derive(Deserialize)]
+struct DrugInfo {
+    #[serde(rename = "rxclassMinConceptItem")]
+    class: ClassItem,
+    #[serde(default)]
+    rela: String,
+}
+
+#[derive(Deserialize)]
+struct ClassItem {
+    #[serde(rename = "classId")]
+    class_id: String,
+    #[serde(rename = "className", default)]
+    class_name: String,
+}
+
+pub(crate) fn parse_atc_classes(body: &str) -> Result<Vec<AtcClass>, RxNormError> {
+    let response: ClassesByRxcuiResponse = parse_json(body)?;
+    let mut classes: Vec<AtcClass> = Vec::new();
+    for info in response.drug_info_list.drug_info {
+        let class = AtcClass {
+            class_id: info.class.class_id,
+            class_name: info.class.class_name,
+            rela: info.rela,
+        };
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    Ok(classes)
+}
+
+pub(crate) fn parse_class_members(body: &str) -> Result<Vec<RelatedConcept>, RxNormError> {
+    let response: ClassMembersResponse = parse_json(body)?;
+    response
+        .drug_member_group
+        .drug_member
+        .into_iter()
+        .map(|member| member.min_concept.into_concept())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_class_members() {
+        let body = r#"{"drugMemberGroup":{"drugMember":[
+            {"minConcept":{"rxcui":"18867","name":"benazepril","tty":"IN"},
+             "nodeAttr":[{"attrName":"SourceId","attrValue":"C09AA07"}]},
+            {"minConcept":{"rxcui":"1998","name":"captopril","tty":"IN"}}]}}"#;
+        let ids: Vec<u32> = parse_class_members(body)
+            .unwrap()
+            .iter()
+            .map(|c| c.rxcui)
+            .collect();
+        assert_eq!(vec![18867, 1998], ids);
+        assert!(parse_class_members("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_atc_classes_lists_each_class_once() {
+        let body = r#"{"rxclassDrugInfoList":{"rxclassDrugInfo":[
+            {"minConcept":{"rxcui":"161","name":"acetaminophen","tty":"IN"},
+             "rxclassMinConceptItem":{"classId":"N02BE","className":"Anilides","classType":"ATC1-4"},
+             "rela":"","relaSource":"ATC"},
+            {"minConcept":{"rxcui":"161","name":"acetaminophen","tty":"IN"},
+             "rxclassMinConceptItem":{"classId":"N02BE","className":"Anilides","classType":"ATC1-4"},
+             "rela":"","relaSource":"ATC"}]}}"#;
+        assert_eq!(
+            vec![AtcClass {
+                class_id: String::from("N02BE"),
+                class_name: String::from("Anilides"),
+                rela: String::new(),
+            }],
+            parse_atc_classes(body).unwrap()
+        );
+        assert!(parse_atc_classes("{}").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use async_trait::async_trait;
+
+use crate::{
+    ApproximateCandidate, ConceptGroup, HistoryStatus, IdType, Interaction, PropCategory,
+    PropConcept, RxConceptProperties, RxNormClient, RxNormError, RxNormVersion, RxTermsInfo,
+    TermType,
+};
+
+/// The RxNav queries of [`RxNormClient`], as a trait so application code can
+/// be tested against a mock instead of the live server.
+///
+/// Implement it with `#[async_trait]`. Helpers that combine several calls,
+/// such as [`RxNormClient::migrate_rxcuis`], stay on the client.
+///
+/// # Examples
+/// ```rust
+/// use rxnormalizer::{RxNorm, RxNormError};
+///
+/// async fn first_name<R: RxNorm>(rx: &R, drug: &str) -> Result<Option<String>, RxNormError> {
+///     match rx.find_rxcui(drug).await? {
+///         Some(ids) if !ids.is_empty() => rx.get_name(ids[0]).await,
+///         _ => Ok(None),
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait RxNorm {
+    async fn find_rxcui(&self, drug: &str) -> Result<Option<Vec<u32>>, RxNormError>;
+
+    async fn find_rxcui_by_id(
+        &self,
+        id: &str,
+        idtype: IdType,
+    ) -> Result<Option<Vec<u32>>, RxNormError>;
+
+    async fn find_rxcui_by_ndc(&self, ndc: &str) -> Result<Option<Vec<u32>>, RxNormError>;
+
+    async fn get_properties(&self, rxcui: u32) -> Result<Option<RxConceptProperties>, RxNormError>;
+
+    async fn get_name(&self, rxcui: u32) -> Result<Option<String>, RxNormError>;
+
+    async fn get_all_properties(
+        &self,
+        rxcui: u32,
+        categories: &[PropCategory],
+    ) -> Result<Vec<PropConcept>, RxNormError>;
+
+    async fn get_property(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+    ) -> Result<Option<String>, RxNormError>;
+
+    async fn get_rxterms(&self, rxcui: u32) -> Result<Option<RxTermsInfo>, RxNormError>;
+
+    async fn approximate_match(
+        &self,
+        term: &str,
+        max_entries: u32,
+    ) -> Result<Vec<ApproximateCandidate>, RxNormError>;
+
+    async fn spelling_suggestions(&self, name: &str) -> Result<Vec<String>, RxNormError>;
+
+    async fn get_related_by_type(
+        &self,
+        rxcui: u32,
+        ttys: &[TermType],
+    ) -> Result<Vec<ConceptGroup>, RxNormError>;
+
+    async fn get_all_related(&self, rxcui: u32) -> Result<Vec<ConceptGroup>, RxNormError>;
+
+    async fn get_drugs(&self, name: &str) -> Result<Vec<ConceptGroup>, RxNormError>;
+
+    async fn find_interactions(&self, rxcui: u32) -> Result<Vec<Interaction>, RxNormError>;
+
+    async fn find_interactions_between(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<Vec<Interaction>, RxNormError>;
+
+    async fn history_status(&self, rxcui: u32) -> Result<HistoryStatus, RxNormError>;
+
+    async fn get_term_types(&self) -> Result<Vec<String>, RxNormError>;
+
+    async fn get_version(&self) -> Result<RxNormVersion, RxNormError>;
+}
+
+#[async_trait]
+impl RxNorm for RxNormClient {
+    async fn find_rxcui(&self, drug: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        RxNormClient::find_rxcui(self, drug).await
+    }
+
+    async fn find_rxcui_by_id(
+        &self,
+        id: &str,
+        idtype: IdType,
+    ) -> Result<Option<Vec<u32>>, RxNormError> {
+        RxNormClient::find_rxcui_by_id(self, id, idtype).await
+    }
+
+    async fn find_rxcui_by_ndc(&self, ndc: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+        RxNormClient::find_rxcui_by_ndc(self, ndc).await
+    }
+
+    async fn get_properties(&self, rxcui: u32) -> Result<Option<RxConceptProperties>, RxNormError> {
+        RxNormClient::get_properties(self, rxcui).await
+    }
+
+    async fn get_name(&self, rxcui: u32) -> Result<Option<String>, RxNormError> {
+        RxNormClient::get_name(self, rxcui).await
+    }
+
+    async fn get_all_properties(
+        &self,
+        rxcui: u32,
+        categories: &[PropCategory],
+    ) -> Result<Vec<PropConcept>, RxNormError> {
+        RxNormClient::get_all_properties(self, rxcui, categories).await
+    }
+
+    async fn get_property(
+        &self,
+        rxcui: u32,
+        prop_name: &str,
+    ) -> Result<Option<String>, RxNormError> {
+        RxNormClient::get_property(self, rxcui, prop_name).await
+    }
+
+    async fn get_rxterms(&self, rxcui: u32) -> Result<Option<RxTermsInfo>, RxNormError> {
+        RxNormClient::get_rxterms(self, rxcui).await
+    }
+
+    async fn approximate_match(
+        &self,
+        term: &str,
+        max_entries: u32,
+    ) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+        RxNormClient::approximate_match(self, term, max_entries).await
+    }
+
+    async fn spelling_suggestions(&self, name: &str) -> Result<Vec<String>, RxNormError> {
+        RxNormClient::spelling_suggestions(self, name).await
+    }
+
+    async fn get_related_by_type(
+        &self,
+        rxcui: u32,
+        ttys: &[TermType],
+    ) -> Result<Vec<ConceptGroup>, RxNormError> {
+        RxNormClient::get_related_by_type(self, rxcui, ttys).await
+    }
+
+    async fn get_all_related(&self, rxcui: u32) -> Result<Vec<ConceptGroup>, RxNormError> {
+        RxNormClient::get_all_related(self, rxcui).await
+    }
+
+    async fn get_drugs(&self, name: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+        RxNormClient::get_drugs(self, name).await
+    }
+
+    async fn find_interactions(&self, rxcui: u32) -> Result<Vec<Interaction>, RxNormError> {
+        RxNormClient::find_interactions(self, rxcui).await
+    }
+
+    async fn find_interactions_between(
+        &self,
+        rxcuis: &[u32],
+    ) -> Result<Vec<Interaction>, RxNormError> {
+        RxNormClient::find_interactions_between(self, rxcuis).await
+    }
+
+    async fn history_status(&self, rxcui: u32) -> Result<HistoryStatus, RxNormError> {
+        RxNormClient::history_status(self, rxcui).await
+    }
+
+    async fn get_term_types(&self) -> Result<Vec<String>, RxNormError> {
+        RxNormClient::get_term_types(self).await
+    }
+
+    async fn get_version(&self) -> Result<RxNormVersion, RxNormError> {
+        RxNormClient::get_version(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in that knows a single drug, as an application test would use.
+    struct OneDrug;
+
+    #[async_trait]
+    impl RxNorm for OneDrug {
+        async fn find_rxcui(&self, drug: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+            Ok(Some(vec![1151]).filter(|_| drug == "vit-c"))
+        }
+        async fn find_rxcui_by_id(
+            &self,
+            _: &str,
+            _: IdType,
+        ) -> Result<Option<Vec<u32>>, RxNormError> {
+            Ok(None)
+        }
+        async fn find_rxcui_by_ndc(&self, _: &str) -> Result<Option<Vec<u32>>, RxNormError> {
+            Ok(None)
+        }
+        async fn get_properties(&self, _: u32) -> Result<Option<RxConceptProperties>, RxNormError> {
+            Ok(None)
+        }
+        async fn get_name(&self, rxcui: u32) -> Result<Option<String>, RxNormError> {
+            Ok(Some(String::from("ascorbic acid")).filter(|_| rxcui == 1151))
+        }
+        async fn get_all_properties(
+            &self,
+            _: u32,
+            _: &[PropCategory],
+        ) -> Result<Vec<PropConcept>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn get_property(&self, _: u32, _: &str) -> Result<Option<String>, RxNormError> {
+            Ok(None)
+        }
+        async fn get_rxterms(&self, _: u32) -> Result<Option<RxTermsInfo>, RxNormError> {
+            Ok(None)
+        }
+        async fn approximate_match(
+            &self,
+            _: &str,
+            _: u32,
+        ) -> Result<Vec<ApproximateCandidate>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn spelling_suggestions(&self, _: &str) -> Result<Vec<String>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn get_related_by_type(
+            &self,
+            _: u32,
+            _: &[TermType],
+        ) -> Result<Vec<ConceptGroup>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn get_all_related(&self, _: u32) -> Result<Vec<ConceptGroup>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn get_drugs(&self, _: &str) -> Result<Vec<ConceptGroup>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn find_interactions(&self, _: u32) -> Result<Vec<Interaction>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn find_interactions_between(
+            &self,
+            _: &[u32],
+        ) -> Result<Vec<Interaction>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn history_status(&self, _: u32) -> Result<HistoryStatus, RxNormError> {
+            Err(RxNormError::Timeout)
+        }
+        async fn get_term_types(&self) -> Result<Vec<String>, RxNormError> {
+            Ok(Vec::new())
+        }
+        async fn get_version(&self) -> Result<RxNormVersion, RxNormError> {
+            Err(RxNormError::Timeout)
+        }
+    }
+
+    async fn first_name<R: RxNorm>(rx: &R, drug: &str) -> Result<Option<String>, RxNormError> {
+        match rx.find_rxcui(drug).await? {
+            Some(ids) if !ids.is_empty() => rx.get_name(ids[0]).await,
+            _ => Ok(None),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generic_code_runs_against_a_mock() {
+        assert_eq!(
+            Some(String::from("ascorbic acid")),
+            first_name(&OneDrug, "vit-c").await.unwrap()
+        );
+        assert_eq!(None, first_name(&OneDrug, "aspirin").await.unwrap());
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_json, rxcui_path, RxNormClient, RxNormError};
+
+/// Patient-friendly naming of a concept from the RxTerms API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RxTermsInfo {
+    pub display_name: String,
+    pub route: String,
+    pub strength: String,
+    pub dose_form_name: String,
+    /// Empty for generic drugs.
+    pub brand_name: String,
+}
+
+impl RxNormClient {
+    /// Fetches the RxTerms display name, route, strength and dose form of a
+    /// concept.
+    ///
+    /// Returns `Ok(None)` when the concept has no RxTerms entry, which is
+    /// usual for ingredient-level concepts.
+    pub async fn get_rxterms(&self, rxcui: u32) -> Result<Option<RxTermsInfo>, RxNormError> {
+        let url = self.url(&format!("/RxTerms{}", rxcui_path(rxcui, "allinfo.json")?));
+        let body = self.get(&url, &[]).await?;
+        parse_rxterms(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct RxTermsResponse {
+    #[serde(rename = "rxtermsProperties")]
+    rxterms_properties: Option<RawRxTerms>,
+}
+
+#[derive(Deserialize)]
+struct RawRxTerms {
+    #[serde(rename = "displayName", default)]
+    display_name: String,
+    #[serde(default)]
+    route: String,
+    #[serde(default)]
+    strength: String,
+    #[serde(rename = "rxnormDoseForm", default)]
+    rxnorm_dose_form: String,
+    #[serde(rename = "brandName", default)]
+    brand_name: String,
+}
+
+pub(crate) fn parse_rxterms(body: &str) -> Result<Option<RxTermsInfo>, RxNormError> {
+    let response: RxTermsResponse = parse_json(body)?;
+    Ok(response
+        .rxterms_properties
+        .filter(|props| !props.display_name.is_empty())
+        .map(|props| RxTermsInfo {
+            display_name: props.display_name,
+            route: props.route,
+            strength: props.strength,
+            dose_form_name: props.rxnorm_dose_form,
+            brand_name: props.brand_name,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rxterms() {
+        let body = r#"{"rxtermsProperties":{"brandName":"","displayName":"Acetaminophen (Oral Pill)",
+            "synonym":"APAP","strength":"500 mg","rxtermsDoseForm":"Tab","route":"Oral Pill",
+            "termType":"SCD","rxcui":"198440","rxnormDoseForm":"Oral Tablet","suppress":""}}"#;
+        let info = parse_rxterms(body).unwrap().expect("RxTerms missing");
+        assert_eq!("Acetaminophen (Oral Pill)", info.display_name);
+        assert_eq!("500 mg", info.strength);
+        assert_eq!("Oral Tablet", info.dose_form_name);
+    }
+
+    #[test]
+    fn test_parse_missing_rxterms() {
+        assert_eq!(None, parse_rxterms("{}").unwrap());
+    }
+}
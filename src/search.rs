@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{join_list, RxNormError};
+
+/// How RxNav matches a drug name in a name search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SearchMode {
+    /// Only exact matches (`search=0`).
+    Exact,
+    /// Only matches after RxNav's string normalization (`search=1`).
+    Normalized,
+    /// Exact matches, falling back to normalized ones when there are none (`search=2`).
+    #[default]
+    ExactThenNormalized,
+}
+
+impl SearchMode {
+    /// The value RxNav expects in the `search` query parameter.
+    pub fn as_param(self) -> &'static str {
+        match self {
+            SearchMode::Exact => "0",
+            SearchMode::Normalized => "1",
+            SearchMode::ExactThenNormalized => "2",
+        }
+    }
+}
+
+/// Which concepts RxCUI searches consider, through RxNav's `allsrc` and
+/// `srclist` parameters. Searches beyond current RxNorm concepts may need a
+/// license for proprietary sources.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SourceScope {
+    /// Only current RxNorm concepts, RxNav's default.
+    #[default]
+    CurrentOnly,
+    /// Concepts from every source (`allsrc=1`).
+    IncludeAll,
+    /// Concepts from the listed sources, e.g. `MMSL` (`allsrc=1&srclist=...`).
+    Sources(Vec<String>),
+}
+
+impl SourceScope {
+    /// The query parameters this scope adds to a search.
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SourceScope::CurrentOnly => Vec::new(),
+            SourceScope::IncludeAll => vec![("allsrc", String::from("1"))],
+            SourceScope::Sources(sources) => vec![
+                ("allsrc", String::from("1")),
+                ("srclist", join_list(sources)),
+            ],
+        }
+    }
+}
+
+/// Rejects `/rxcui.json` parameter combinations that RxNav answers with an
+/// empty result instead of an error, such as an `idtype` search that also
+/// carries a `name` or a `search` mode.
+pub(crate) fn check_search_params<'a, I>(params: I) -> Result<(), RxNormError>
+where
+    I: IntoIterator<Item = &'a (&'a str, &'a str)>,
+{
+    let params: Vec<(&str, &str)> = params.into_iter().copied().collect();
+    let value = |key: &str| params.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+    let conflict = |msg: &str| Err(RxNormError::InvalidInput(msg.to_string()));
+    if value("idtype").is_some() {
+        if value("name").is_some() {
+            return conflict("an idtype search cannot also search by name");
+        }
+        if value("search").is_some() {
+            return conflict("search modes apply to name searches, not idtype searches");
+        }
+        if value("id").is_none_or(str::is_empty) {
+            return conflict("an idtype search needs an id");
+        }
+    }
+    if value("srclist").is_some_and(|sources| sources.trim().is_empty()) {
+        return conflict("SourceScope::Sources needs at least one source");
+    }
+    Ok(())
+}
+
+/// `true` is the historical "normalize" flag, which searched exact then normalized.
+impl From<bool> for SearchMode {
+    fn from(normalize: bool) -> Self {
+        if normalize {
+            SearchMode::ExactThenNormalized
+        } else {
+            SearchMode::Exact
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_scope_params() {
+        assert!(SourceScope::CurrentOnly.params().is_empty());
+        let sources = SourceScope::Sources(vec![String::from("MMSL"), String::from("GS")]);
+        assert_eq!(
+            vec![
+                ("allsrc", String::from("1")),
+                ("srclist", String::from("MMSL GS"))
+            ],
+            sources.params()
+        );
+    }
+
+    #[test]
+    fn test_conflicting_search_params_are_rejected() {
+        let check = |params: &[(&str, &str)]| check_search_params(params);
+        assert!(check(&[("name", "aspirin"), ("search", "2")]).is_ok());
+        assert!(check(&[("idtype", "ATC"), ("id", "N02BE01"), ("allsrc", "1")]).is_ok());
+        for params in [
+            &[("idtype", "ATC"), ("id", "N02BE01"), ("name", "aspirin")][..],
+            &[("idtype", "ATC"), ("id", "N02BE01"), ("search", "2")][..],
+            &[("idtype", "ATC"), ("id", "")][..],
+            &[("name", "aspirin"), ("allsrc", "1"), ("srclist", "")][..],
+        ] {
+            assert!(matches!(check(params), Err(RxNormError::InvalidInput(_))));
+        }
+    }
+}
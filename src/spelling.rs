@@ -0,0 +1,60 @@
+use serde::Deserialize;
+
+use crate::{parse_json, RxNormClient, RxNormError};
+
+impl RxNormClient {
+    /// Returns RxNav's spelling suggestions for `name`, or an empty vector
+    /// when it has none.
+    pub async fn spelling_suggestions(&self, name: &str) -> Result<Vec<String>, RxNormError> {
+        let url = self.url("/spellingsuggestions.json");
+        let body = self.get(&url, &[("name", name)]).await?;
+        parse_suggestions(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct SuggestionResponse {
+    #[serde(rename = "suggestionGroup", default)]
+    suggestion_group: SuggestionGroup,
+}
+
+#[derive(Deserialize, Default)]
+struct SuggestionGroup {
+    #[serde(rename = "suggestionList")]
+    suggestion_list: Option<SuggestionList>,
+}
+
+#[derive(Deserialize)]
+struct SuggestionList {
+    #[serde(default)]
+    suggestion: Vec<String>,
+}
+
+pub(crate) fn parse_suggestions(body: &str) -> Result<Vec<String>, RxNormError> {
+    let response: SuggestionResponse = parse_json(body)?;
+    Ok(response
+        .suggestion_group
+        .suggestion_list
+        .map(|list| list.suggestion)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suggestions() {
+        let body = r#"{"suggestionGroup":{"name":"ambienn","suggestionList":{"suggestion":["ambien","ambien cr"]}}}"#;
+        assert_eq!(
+            vec!["ambien", "ambien cr"],
+            parse_suggestions(body).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_no_suggestions() {
+        let body = r#"{"suggestionGroup":{"name":"qqq","suggestionList":null}}"#;
+        assert!(parse_suggestions(body).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_json, RxNormClient, RxNormError};
+
+/// An RxNorm term type (TTY), such as an ingredient or a branded drug.
+///
+/// Term types are ordered by specificity, from ingredients up through drug
+/// components, forms and drugs to packs, with the branded type just above
+/// its generic counterpart: `IN < PIN < MIN < BN < SCDC < ... < SCD < SBD <
+/// GPCK < BPCK`. Dose forms, synonyms and unknown types sort below all of
+/// them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub enum TermType {
+    /// Ingredient
+    In,
+    /// Precise ingredient
+    Pin,
+    /// Multiple ingredients
+    Min,
+    /// Semantic clinical drug component
+    Scdc,
+    /// Semantic clinical drug form
+    Scdf,
+    /// Semantic clinical drug form group
+    Scdg,
+    /// Semantic clinical drug
+    Scd,
+    /// Generic pack
+    Gpck,
+    /// Brand name
+    Bn,
+    /// Semantic branded drug component
+    Sbdc,
+    /// Semantic branded drug form
+    Sbdf,
+    /// Semantic branded drug group
+    Sbdg,
+    /// Semantic branded drug
+    Sbd,
+    /// Branded pack
+    Bpck,
+    /// Dose form
+    Df,
+    /// Dose form group
+    Dfg,
+    /// Prescribable name
+    Psn,
+    /// Synonym
+    Sy,
+    /// Tall-man synonym
+    Tmsy,
+    /// Any other term type RxNav reports.
+    Other(String),
+}
+
+impl TermType {
+    /// The TTY code as RxNav spells it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TermType::In => "IN",
+            TermType::Pin => "PIN",
+            TermType::Min => "MIN",
+            TermType::Scdc => "SCDC",
+            TermType::Scdf => "SCDF",
+            TermType::Scdg => "SCDG",
+            TermType::Scd => "SCD",
+            TermType::Gpck => "GPCK",
+            TermType::Bn => "BN",
+            TermType::Sbdc => "SBDC",
+            TermType::Sbdf => "SBDF",
+            TermType::Sbdg => "SBDG",
+            TermType::Sbd => "SBD",
+            TermType::Bpck => "BPCK",
+            TermType::Df => "DF",
+            TermType::Dfg => "DFG",
+            TermType::Psn => "PSN",
+            TermType::Sy => "SY",
+            TermType::Tmsy => "TMSY",
+            TermType::Other(tty) => tty,
+        }
+    }
+}
+
+impl TermType {
+    /// The rank of this term type in the specificity order; higher is more
+    /// specific.
+    fn specificity(&self) -> u8 {
+        match self {
+            TermType::Other(_) => 0,
+            TermType::Sy | TermType::Tmsy | TermType::Psn => 1,
+            TermType::Dfg => 2,
+            TermType::Df => 3,
+            TermType::In => 10,
+            TermType::Pin => 11,
+            TermType::Min => 12,
+            TermType::Bn => 13,
+            TermType::Scdc => 20,
+            TermType::Sbdc => 21,
+            TermType::Scdg => 30,
+            TermType::Sbdg => 31,
+            TermType::Scdf => 32,
+            TermType::Sbdf => 33,
+            TermType::Scd => 40,
+            TermType::Sbd => 41,
+            TermType::Gpck => 50,
+            TermType::Bpck => 51,
+        }
+    }
+}
+
+impl Ord for TermType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.specificity()
+            .cmp(&other.specificity())
+            .then_with(|| self.as_str().cmp(other.as_str()))
+    }
+}
+
+impl PartialOrd for TermType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The most specific of `concepts` by term type, e.g. to prefer a clinical
+/// drug over its ingredient among the ids a search returned. Ties go to the
+/// first; no concepts gives `None`.
+pub fn most_specific<I>(concepts: I) -> Option<(u32, TermType)>
+where
+    I: IntoIterator<Item = (u32, TermType)>,
+{
+    concepts.into_iter().fold(None, |best, concept| match best {
+        Some(best) if best.1 >= concept.1 => Some(best),
+        _ => Some(concept),
+    })
+}
+
+impl From<&str> for TermType {
+    fn from(tty: &str) -> Self {
+        match tty {
+            "IN" => TermType::In,
+            "PIN" => TermType::Pin,
+            "MIN" => TermType::Min,
+            "SCDC" => TermType::Scdc,
+            "SCDF" => TermType::Scdf,
+            "SCDG" => TermType::Scdg,
+            "SCD" => TermType::Scd,
+            "GPCK" => TermType::Gpck,
+            "BN" => TermType::Bn,
+            "SBDC" => TermType::Sbdc,
+            "SBDF" => TermType::Sbdf,
+            "SBDG" => TermType::Sbdg,
+            "SBD" => TermType::Sbd,
+            "BPCK" => TermType::Bpck,
+            "DF" => TermType::Df,
+            "DFG" => TermType::Dfg,
+            "PSN" => TermType::Psn,
+            "SY" => TermType::Sy,
+            "TMSY" => TermType::Tmsy,
+            other => TermType::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for TermType {
+    fn from(tty: String) -> Self {
+        TermType::from(tty.as_str())
+    }
+}
+
+impl From<TermType> for String {
+    fn from(tty: TermType) -> Self {
+        match tty {
+            TermType::Other(tty) => tty,
+            tty => tty.as_str().to_string(),
+        }
+    }
+}
+
+impl AsRef<str> for TermType {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for TermType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl RxNormClient {
+    /// Lists the term type codes RxNav knows about.
+    pub async fn get_term_types(&self) -> Result<Vec<String>, RxNormError> {
+        let url = self.url("/termtypes.json");
+        let body = self.get(&url, &[]).await?;
+        parse_term_types(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct TermTypesResponse {
+    #[serde(rename = "termTypeList", default)]
+    term_type_list: TermTypeList,
+}
+
+#[derive(Deserialize, Default)]
+struct TermTypeList {
+    #[serde(rename = "termType", default)]
+    term_type: Vec<String>,
+}
+
+pub(crate) fn parse_term_types(body: &str) -> Result<Vec<String>, RxNormError> {
+    let response: TermTypesResponse = parse_json(body)?;
+    Ok(response.term_type_list.term_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_term_types() {
+        let body = r#"{"termTypeList":{"termType":["BN","BPCK","DF"]}}"#;
+        assert_eq!(vec!["BN", "BPCK", "DF"], parse_term_types(body).unwrap());
+    }
+
+    #[test]
+    fn test_term_type_round_trips() {
+        assert_eq!(TermType::Scd, TermType::from("SCD"));
+        assert_eq!("SBD", TermType::Sbd.as_str());
+        assert_eq!(TermType::Other(String::from("XYZ")), TermType::from("XYZ"));
+    }
+
+    #[test]
+    fn test_term_type_serializes_as_code() {
+        let json = serde_json::to_string(&vec![TermType::Scd, TermType::from("XYZ")]).unwrap();
+        assert_eq!(r#"["SCD","XYZ"]"#, json);
+        let back: Vec<TermType> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            vec![TermType::Scd, TermType::Other(String::from("XYZ"))],
+            back
+        );
+    }
+
+    #[test]
+    fn test_term_types_order_by_specificity() {
+        let mut ttys: Vec<TermType> = ["BPCK", "SBD", "IN", "XYZ", "SCD", "SCDC", "PIN"]
+            .iter()
+            .map(|&tty| TermType::from(tty))
+            .collect();
+        ttys.sort();
+        let codes: Vec<&str> = ttys.iter().map(TermType::as_str).collect();
+        assert_eq!(
+            vec!["XYZ", "IN", "PIN", "SCDC", "SCD", "SBD", "BPCK"],
+            codes
+        );
+    }
+
+    #[test]
+    fn test_most_specific_prefers_first_of_ties() {
+        let concepts = vec![
+            (161, TermType::In),
+            (313782, TermType::Scd),
+            (198440, TermType::Scd),
+            (1, TermType::Sy),
+        ];
+        assert_eq!(Some((313782, TermType::Scd)), most_specific(concepts));
+        assert_eq!(None, most_specific(Vec::new()));
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::atomic::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_json, RxNormClient, RxNormError};
+
+/// Month abbreviations as RxNav writes them in release names.
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The RxNorm release an RxNav server has loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RxNormVersion {
+    /// The release, e.g. `07-Oct-2024`.
+    pub version: String,
+    /// The version of the RxNav API software.
+    pub api_version: String,
+}
+
+impl RxNormClient {
+    /// Reports which RxNorm release the server is serving. Self-hosted
+    /// RxNav-in-a-Box installs can lag behind the public server.
+    ///
+    /// When the release differs from the one last reported, the client's
+    /// cache is emptied, since its entries may map to superseded concepts.
+    pub async fn get_version(&self) -> Result<RxNormVersion, RxNormError> {
+        let url = self.url("/version.json");
+        let body = self.get(&url, &[]).await?;
+        let version = parse_version(&body)?;
+        if let Some(cache) = &self.cache {
+            cache.observe_release(&version.version);
+        }
+        Ok(version)
+    }
+
+    /// Sends a cheap [`get_version`](Self::get_version) request so DNS
+    /// resolution and the TLS handshake happen before real traffic. Calling
+    /// this during startup keeps that cost off the first user-facing lookup;
+    /// later requests reuse the pooled connection.
+    pub async fn warm_up(&self) -> Result<(), RxNormError> {
+        self.get_version().await.map(|_| ())
+    }
+
+    /// Fails with [`RxNormError::VersionTooOld`] if the server's release is
+    /// older than the one set with
+    /// [`require_min_version`](crate::RxNormClientBuilder::require_min_version).
+    ///
+    /// Requests check this themselves before the first one is sent; calling
+    /// it at startup surfaces a stale server before any real work. Without a
+    /// required version this does nothing.
+    pub async fn check_version(&self) -> Result<(), RxNormError> {
+        let required = match &self.min_version {
+            Some(required) => required,
+            None => return Ok(()),
+        };
+        let url = self.url("/version.json");
+        let (body, _) = self.send_before(&url, &[], None).await?;
+        check_release(&parse_version(&body)?.version, required)?;
+        self.version_checked.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Whether a required version is set and has not been confirmed yet.
+    pub(crate) fn needs_version_check(&self) -> bool {
+        self.min_version.is_some() && !self.version_checked.load(Ordering::Relaxed)
+    }
+}
+
+/// Compares RxNorm release names such as `07-Oct-2024`. A release that
+/// cannot be read fails the check, since it cannot be shown to be recent.
+pub(crate) fn check_release(found: &str, required: &str) -> Result<(), RxNormError> {
+    let required_date = parse_release(required).ok_or_else(|| {
+        RxNormError::Config(format!(
+            "required RxNorm release '{}' is not like 07-Oct-2024",
+            required
+        ))
+    })?;
+    match parse_release(found) {
+        Some(found_date) if found_date >= required_date => Ok(()),
+        _ => Err(RxNormError::VersionTooOld {
+            found: found.to_string(),
+            required: required.to_string(),
+        }),
+    }
+}
+
+/// A release name such as `07-Oct-2024` as `(year, month, day)`.
+fn parse_release(release: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = release.trim().split('-');
+    let day = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? as u32 + 1;
+    let year = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    #[serde(default)]
+    version: String,
+    #[serde(rename = "apiVersion", default)]
+    api_version: String,
+}
+
+pub(crate) fn parse_version(body: &str) -> Result<RxNormVersion, RxNormError> {
+    let response: VersionResponse = parse_json(body)?;
+    Ok(RxNormVersion {
+        version: response.version,
+        api_version: response.api_version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        let body = r#"{"version":"07-Oct-2024","apiVersion":"3.1.256"}"#;
+        let version = parse_version(body).unwrap();
+        assert_eq!("07-Oct-2024", version.version);
+        assert_eq!("3.1.256", version.api_version);
+    }
+
+    #[test]
+    fn test_release_comparison() {
+        assert!(check_release("07-Oct-2024", "02-Sep-2024").is_ok());
+        assert!(check_release("07-Oct-2024", "07-Oct-2024").is_ok());
+        assert!(matches!(
+            check_release("04-Dec-2023", "02-Sep-2024"),
+            Err(RxNormError::VersionTooOld { .. })
+        ));
+        assert!(matches!(
+            check_release("latest", "02-Sep-2024"),
+            Err(RxNormError::VersionTooOld { .. })
+        ));
+        assert!(matches!(
+            check_release("07-Oct-2024", "2024"),
+            Err(RxNormError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_old_server_is_refused_before_the_request() {
+        let (addr, requests) =
+            crate::test_server::serve_recording(vec![crate::test_server::response(
+                "200 OK",
+                &[],
+                r#"{"version":"04-Dec-2023","apiVersion":"3.1.174"}"#,
+            )])
+            .await;
+        let rx_client = RxNormClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .require_min_version("02-Sep-2024")
+            .build();
+        let actual = rx_client.find_rxcui("aspirin").await;
+        assert!(matches!(
+            actual,
+            Err(RxNormError::VersionTooOld { found, .. }) if found == "04-Dec-2023"
+        ));
+        assert_eq!("GET /version.json HTTP/1.1", requests.recv().unwrap());
+        assert!(requests.try_recv().is_err());
+    }
+}